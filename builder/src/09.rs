@@ -1,62 +1,515 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{DeriveInput, Error, Ident, spanned::Spanned, parse_macro_input};
+use syn::{DeriveInput, Error, Ident, ext::IdentExt, spanned::Spanned, parse_macro_input};
 
 pub fn token_stream(input: TokenStream) -> TokenStream {
     let derive_input = parse_macro_input!(input as DeriveInput);
 
-    let builder_stream = generate_builder(&derive_input);
-    let impl_stream = generate_impl(&derive_input);
+    if let Err(err) = check_attrs(&derive_input) {
+        return err.into_compile_error().into();
+    }
+    let targets = match parse_targets(&derive_input) {
+        Ok(targets) => targets,
+        Err(err) => return err.into_compile_error().into(),
+    };
+    let builder_stream = targets.iter().map(generate_builder);
+    let impl_stream = generate_impl(&derive_input, &targets);
     let stream = quote!{
-        #builder_stream
+        #(#builder_stream)*
         #impl_stream
     };
     stream.into()
 }
 
+/// A struct, or a variant of an enum, to generate a builder for.
+struct Target<'a> {
+    input: &'a DeriveInput,
+    /// Fields of the struct or of the variant.
+    fields: &'a syn::Fields,
+    /// Path of the value constructed by build, Command or Shape::Circle.
+    path: proc_macro2::TokenStream,
+    /// CommandBuilder or ShapeCircleBuilder.
+    builder_ident: Ident,
+    /// Visibility of the builder and of its error enum.
+    vis: syn::Visibility,
+    /// Root of the core paths in generated code, std, or core with no_std.
+    core: proc_macro2::TokenStream,
+    /// Root of the alloc paths in generated code, std, or alloc with no_std.
+    alloc: proc_macro2::TokenStream,
+    /// builder or circle_builder.
+    method_ident: Ident,
+}
+
+/// Parse the targets of the derive input.
+/// struct Command { ... }                 => [Command]
+/// enum Shape { Circle { ... }, Empty }   => [Shape::Circle]
+fn parse_targets(input: &DeriveInput) -> syn::Result<Vec<Target<'_>>> {
+    let ident = &input.ident;
+    let name = parse_builder_name(input)?;
+    let vis = parse_builder_vis(input)?;
+    let (core, alloc) = if parse_no_std(input)?.is_some() {
+        (quote!(core), quote!(alloc))
+    } else {
+        (quote!(std), quote!(std))
+    };
+    match &input.data {
+        syn::Data::Struct(syn::DataStruct { fields, .. }) => Ok(vec![Target {
+            input,
+            fields,
+            path: quote!(#ident),
+            builder_ident: name.unwrap_or_else(|| Ident::new(&format!("{}Builder", ident), input.span())),
+            vis,
+            core,
+            alloc,
+            method_ident: Ident::new("builder", input.span()),
+        }]),
+        // A single name cannot be given to the builders of all variants.
+        syn::Data::Enum(_) if name.is_some() => Err(Error::new_spanned(
+            name.unwrap(),
+            "`name` is not supported on enums, the builders are named after the variants",
+        )),
+        // Unit variants have nothing to build.
+        syn::Data::Enum(syn::DataEnum { variants, .. }) => Ok(variants.iter().filter(|variant| {
+            !matches!(variant.fields, syn::Fields::Unit)
+        }).map(|variant| {
+            let variant_ident = &variant.ident;
+            Target {
+                input,
+                fields: &variant.fields,
+                path: quote!(#ident::#variant_ident),
+                builder_ident: Ident::new(&format!("{}{}Builder", ident, variant_ident), variant.span()),
+                vis: vis.clone(),
+                core: core.clone(),
+                alloc: alloc.clone(),
+                method_ident: Ident::new(&format!("{}_builder", to_snake_case(&variant_ident.to_string())), variant.span()),
+            }
+        }).collect()),
+        syn::Data::Union(_) => Err(Error::new_spanned(input, "unexpected derive input")),
+    }
+}
+
 /// Generate builder struct.
 /// pub struct CommandBuilder { ... }.
-fn generate_builder(input: &DeriveInput) -> proc_macro2::TokenStream {
-    let vis = &input.vis;
-    let builder_name = format!("{}Builder", input.ident.to_string());
-    let builder_ident = Ident::new(&builder_name, input.span());
+///
+/// Generics of the struct are carried over to the builder.
+/// pub struct RequestBuilder<'a, B: Body> { ... }.
+fn generate_builder(target: &Target) -> proc_macro2::TokenStream {
+    let core = &target.core;
+    let input = target.input;
+    let vis = &target.vis;
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let builder_ident = &target.builder_ident;
+
+    let optional_field_stream = parse_optional_fields(target, false);
+
+    let typestate = parse_struct_attr_flag(input, "builder", "typestate");
+    if let Err(err) = typestate {
+        return err.into_compile_error();
+    }
+    let typestate = typestate.unwrap();
+    if let (true, Some(field)) = (typestate, target.fields.iter().find(|field| is_sub_builder(field) && !is_skipped(field))) {
+        return Error::new_spanned(field, "`sub_builder` is not supported by typestate builders").into_compile_error();
+    }
+
+    if let Err(err) = parse_struct_attr(input, "builder", "setter").and_then(parse_setter_into) {
+        return err.into_compile_error();
+    }
+    if let Err(err) = parse_struct_attr(input, "builder", "setter").and_then(|meta| parse_setter_vis_meta(meta.as_ref())) {
+        return err.into_compile_error();
+    }
+    if let Err(err) = parse_struct_attr(input, "builder", "setter").and_then(|meta| parse_setter_strip_option(meta.as_ref())) {
+        return err.into_compile_error();
+    }
+
+    let validate = parse_build_fn_validate(input);
+    if let Err(err) = validate {
+        return err.into_compile_error();
+    }
+    let validate = validate.unwrap();
+
+    let pattern = parse_struct_pattern(input);
+    if let Err(err) = pattern {
+        return err.into_compile_error();
+    }
+    let pattern = pattern.unwrap();
+
+    let constant = parse_struct_attr_flag(input, "builder", "const");
+    if let Err(err) = constant {
+        return err.into_compile_error();
+    }
+    let constant = constant.unwrap();
+    if let (true, Err(err)) = (constant, check_const_builder(target)) {
+        return err.into_compile_error();
+    }
+
+    let derives = parse_builder_derives(input);
+    if let Err(err) = derives {
+        return err.into_compile_error();
+    }
+    let mut derives = derives.unwrap();
+    // Immutable setters clone the builder.
+    if pattern == Pattern::Immutable && !derives.iter().any(|path| path.is_ident("Clone")) {
+        derives.push(syn::parse_quote!(Clone));
+    }
+    let derive_stream = if derives.is_empty() {
+        quote!()
+    } else {
+        quote!(#[derive(#(#derives),*)])
+    };
+    let derives_debug = derives.iter().any(|path| path.is_ident("Debug"));
+    let builder_doc = doc_attr(format!("Builder of [`{}`].", target_name(target)));
+    let introspection_stream = generate_introspection_methods(target);
+
+    let from_env = parse_from_env(input);
+    if let Err(err) = from_env {
+        return err.into_compile_error();
+    }
+    let from_env_stream = match from_env.unwrap() {
+        Some(from_env) => generate_from_env_methods(target, &from_env),
+        None => quote!(),
+    };
 
-    let optional_field_stream = parse_optional_fields(input, false);
-    if let Err(err) = optional_field_stream {
+    let cli = parse_cli(input);
+    if let Err(err) = cli {
         return err.into_compile_error();
     }
-    let optional_field_stream = optional_field_stream.unwrap();
+    let cli_stream = if cli.unwrap() {
+        generate_cli_methods(target)
+    } else {
+        quote!()
+    };
 
     // There will be no error here if the above runs successfully.
-    let setter_stream = generate_setters(input).unwrap();
+    let setter_stream = generate_setters(target, typestate, pattern, constant);
+
+    let build_method_stream = generate_build_method(target, typestate, pattern, constant);
+
+    let from_impl_stream = generate_from_impl(target, typestate);
+
+    let target_ty = target_type(input);
+    if typestate {
+        let (set_ident, unset_ident) = typestate_marker_idents(builder_ident);
+        let params: Vec<Ident> = typestate_params(target).into_iter().flatten().collect();
+        let struct_generics = typestate_generics(generics, &params, Some(&unset_ident));
+        let setter_generics = typestate_generics(generics, &params, None);
+        let (setter_impl_generics, setter_ty_generics, _) = setter_generics.split_for_impl();
+        let set_builder_ty = set_builder_type(target, typestate);
+        let debug_impl_stream = if derives_debug {
+            quote!()
+        } else {
+            generate_debug_impl(target, &setter_generics)
+        };
+        // Only a validation can make a typestate build fail.
+        let error_enum_stream = if validate.is_some() {
+            error_enum(target)
+        } else {
+            quote!()
+        };
+        let set_doc = doc_attr(format!("Marks a field of `{}` as set.", builder_ident));
+        let unset_doc = doc_attr(format!("Marks a field of `{}` as not set.", builder_ident));
+        return quote! {
+            #set_doc
+            #derive_stream
+            #vis struct #set_ident;
+            #unset_doc
+            #derive_stream
+            #vis struct #unset_ident;
+
+            #builder_doc
+            #derive_stream
+            #vis struct #builder_ident #struct_generics #where_clause {
+                #(#optional_field_stream,)*
+                __phantom: #core::marker::PhantomData<(#target_ty, #(#params,)*)>,
+            }
+            impl #setter_impl_generics #builder_ident #setter_ty_generics #where_clause {
+                #(#setter_stream)*
 
-    let build_method_stream = generate_build_method(input);
+                #introspection_stream
+            }
+            impl #impl_generics #set_builder_ty #where_clause {
+                #build_method_stream
+            }
+
+            #debug_impl_stream
+            #from_impl_stream
+            #error_enum_stream
+        };
+    }
 
-    let error_struct_stream = error_struct();
+    let merge_method_stream = generate_merge_method(target);
+    let error_enum_stream = error_enum(target);
+    let debug_impl_stream = if derives_debug {
+        quote!()
+    } else {
+        generate_debug_impl(target, generics)
+    };
     quote! {
-        #vis struct #builder_ident {
-            #(#optional_field_stream),*
+        #builder_doc
+        #derive_stream
+        #vis struct #builder_ident #generics #where_clause {
+            #(#optional_field_stream,)*
+            __phantom: #core::marker::PhantomData<#target_ty>,
         }
-        impl #builder_ident {
+        impl #impl_generics #builder_ident #ty_generics #where_clause {
+            #from_env_stream
+
+            #cli_stream
+
             #(#setter_stream)*
 
+            #introspection_stream
+
+            #merge_method_stream
+
             #build_method_stream
         }
 
-        #error_struct_stream
+        #debug_impl_stream
+        #from_impl_stream
+        #error_enum_stream
+    }
+}
+
+/// Generate the method merging another builder into the builder, the fields
+/// set in other override those of the builder. Each fields are replaced, or
+/// extended with #[builder(merge = "append")], and sub builders are merged.
+/// Typestate builders have no merge, the state of the result is unknown.
+/// pub fn merge(&mut self, other: CommandBuilder) -> &mut Self {
+///     if other.executable.is_some() {
+///         self.executable = other.executable;
+///     }
+///     if let Some(other) = other.args {
+///         Extend::extend(self.args.get_or_insert_with(Default::default), other);
+///     }
+///     ...
+///     self
+/// }
+fn generate_merge_method(target: &Target) -> proc_macro2::TokenStream {
+    let core = &target.core;
+    let field_stream = parse_fields(target, |field| {
+        let ident = &field.ident;
+        let append = match parse_merge_append(field) {
+            Ok(append) => append,
+            Err(err) => return err.into_compile_error(),
+        };
+        if is_sub_builder(field) {
+            return quote! {
+                if let Some(other) = other.#ident {
+                    match &mut self.#ident {
+                        Some(builder) => {
+                            builder.merge(other);
+                        },
+                        None => self.#ident = Some(other),
+                    }
+                }
+            };
+        }
+        if append {
+            return quote! {
+                if let Some(other) = other.#ident {
+                    #core::iter::Extend::extend(self.#ident.get_or_insert_with(#core::default::Default::default), other);
+                }
+            };
+        }
+        quote! {
+            if other.#ident.is_some() {
+                self.#ident = other.#ident;
+            }
+        }
+    });
+    quote! {
+        /// Merges other into the builder, the fields set in other override those of the builder.
+        pub fn merge(&mut self, other: Self) -> &mut Self {
+            #(#field_stream)*
+            self
+        }
+    }
+}
+
+/// Generate the methods telling which fields of the builder are set.
+/// pub fn missing_fields(&self) -> Vec<&'static str> { ... }
+/// pub fn is_executable_set(&self) -> bool {
+///     self.executable.is_some()
+/// }
+///
+/// A field with a sub builder is missing while its builder misses fields.
+fn generate_introspection_methods(target: &Target) -> proc_macro2::TokenStream {
+    let alloc = &target.alloc;
+    let check_none_stream = generate_missing_field_checks(target);
+    let sub_builder_stream = parse_fields(target, |field| {
+        if !is_sub_builder(field) {
+            return quote!();
+        }
+        let ident = &field.ident;
+        let ident_str = ident.as_ref().unwrap().to_string();
+        let ty = &field.ty;
+        quote! {
+            let sub_builder_missing_fields = match &self.#ident {
+                Some(builder) => builder.missing_fields(),
+                None => <#ty>::builder().missing_fields(),
+            };
+            if !sub_builder_missing_fields.is_empty() {
+                missing_fields.push(#ident_str);
+            }
+        }
+    });
+    let is_set_stream = parse_fields(target, |field| {
+        let ident = field.ident.as_ref().unwrap();
+        let is_set_ident = method_ident("is_", ident, "_set");
+        let doc = doc_attr(format!("Whether `{}` is set.", ident));
+        quote! {
+            #doc
+            pub fn #is_set_ident(&self) -> bool {
+                self.#ident.is_some()
+            }
+        }
+    });
+    quote! {
+        /// Names of the required fields that are not set yet.
+        pub fn missing_fields(&self) -> #alloc::vec::Vec<&'static str> {
+            #[allow(unused_mut)]
+            let mut missing_fields = #alloc::vec::Vec::new();
+            #(#check_none_stream)*
+            #(#sub_builder_stream)*
+            missing_fields
+        }
+
+        #(#is_set_stream)*
+    }
+}
+
+/// Generate the checks pushing the name of each required field that is not
+/// set to missing_fields, a Vec of any type converting from &str.
+/// if self.executable.is_none() {
+///     missing_fields.push(From::from("executable"));
+/// }
+fn generate_missing_field_checks(target: &Target) -> Vec<proc_macro2::TokenStream> {
+    let core = &target.core;
+    parse_fields(target, |field| {
+        let ident = &field.ident;
+        let ident_str = ident.as_ref().unwrap().to_string();
+        if !is_required_field(field) {
+            return quote!();
+        }
+        quote! {
+            if self.#ident.is_none() {
+                missing_fields.push(#core::convert::From::from(#ident_str));
+            }
+        }
+    })
+}
+
+/// Generate the Debug impl of the builder, showing whether each field is set
+/// rather than its value, which need not implement Debug.
+/// CommandBuilder { executable: set, args: unset, ... }
+fn generate_debug_impl(target: &Target, generics: &syn::Generics) -> proc_macro2::TokenStream {
+    let core = &target.core;
+    let builder_ident = &target.builder_ident;
+    let builder_str = builder_ident.to_string();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let field_stream = parse_fields(target, |field| {
+        let ident = &field.ident;
+        let ident_str = ident.as_ref().unwrap().to_string();
+        quote! {
+            .field(#ident_str, &#core::format_args!("{}", if self.#ident.is_some() { "set" } else { "unset" }))
+        }
+    });
+    quote! {
+        impl #impl_generics #core::fmt::Debug for #builder_ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut #core::fmt::Formatter<'_>) -> #core::fmt::Result {
+                f.debug_struct(#builder_str)
+                    #(#field_stream)*
+                    .finish()
+            }
+        }
+    }
+}
+
+/// Generate the conversion of a value back into a builder with every field set.
+/// Only structs convert back, a value of an enum may be any of its variants.
+/// impl From<Command> for CommandBuilder {
+///     fn from(value: Command) -> Self {
+///         CommandBuilder {
+///             executable: Some(value.executable),
+///             ...
+///             current_dir: value.current_dir,
+///         }
+///     }
+/// }
+fn generate_from_impl(target: &Target, typestate: bool) -> proc_macro2::TokenStream {
+    let core = &target.core;
+    let input = target.input;
+    if !matches!(input.data, syn::Data::Struct(_)) {
+        return quote!();
+    }
+    let builder_ident = &target.builder_ident;
+    let (impl_generics, _, where_clause) = input.generics.split_for_impl();
+    let target_ty = target_type(input);
+    let builder_ty = set_builder_type(target, typestate);
+    let mut members = target_members(target).into_iter();
+    let field_stream = parse_fields(target, |field| {
+        let ident = &field.ident;
+        let member = members.next().unwrap();
+        let value = if parse_generic_type(field, "Option").is_some() {
+            quote!(value.#member)
+        } else if is_sub_builder(field) {
+            quote!(Some(#core::convert::From::from(value.#member)))
+        } else {
+            quote!(Some(value.#member))
+        };
+        quote! {
+            #ident: #value
+        }
+    });
+    quote! {
+        impl #impl_generics #core::convert::From<#target_ty> for #builder_ty #where_clause {
+            fn from(value: #target_ty) -> Self {
+                #builder_ident {
+                    #(#field_stream,)*
+                    __phantom: #core::marker::PhantomData,
+                }
+            }
+        }
     }
 }
 
-/// Generate impl of input struct.
+/// Generate impl of input struct, with one builder method per target.
 /// impl Command { ... }.
-fn generate_impl(input: &DeriveInput) -> proc_macro2::TokenStream {
+/// impl Shape { pub fn circle_builder() -> ShapeCircleBuilder { ... } ... }.
+fn generate_impl(input: &DeriveInput, targets: &[Target]) -> proc_macro2::TokenStream {
     let ident = &input.ident;
-    let builder_name = format!("{}Builder", input.ident.to_string());
-    let builder_ident = Ident::new(&builder_name, input.span());
-    let builder_method_stream = generate_builder_method(input, &builder_ident);
+    let builder_method_stream = targets.iter().map(generate_builder_method);
+    let to_builder_method_stream = targets.iter().map(generate_to_builder_method);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
     quote! {
-        impl #ident {
-            #builder_method_stream
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#builder_method_stream)*
+            #(#to_builder_method_stream)*
+        }
+    }
+}
+
+/// Generate the method converting a copy of a struct back into its builder.
+/// The higher-ranked bound defers the Clone check to the callers of the method.
+/// pub fn to_builder(&self) -> CommandBuilder where for<'__a> Self: Clone {
+///     From::from(Clone::clone(self))
+/// }
+fn generate_to_builder_method(target: &Target) -> proc_macro2::TokenStream {
+    let core = &target.core;
+    let input = target.input;
+    if !matches!(input.data, syn::Data::Struct(_)) {
+        return quote!();
+    }
+    // Invalid typestate attributes are reported with the builder.
+    let typestate = parse_struct_attr_flag(input, "builder", "typestate").unwrap_or(false);
+    let builder_ty = set_builder_type(target, typestate);
+    let method_vis = builder_method_vis(target);
+    let doc = doc_attr(format!("Creates a [`{}`] with every field set from a copy of the value.", target.builder_ident));
+    quote! {
+        #doc
+        #method_vis fn to_builder(&self) -> #builder_ty where for<'__a> Self: #core::clone::Clone {
+            #core::convert::From::from(#core::clone::Clone::clone(self))
         }
     }
 }
@@ -69,16 +522,25 @@ fn generate_impl(input: &DeriveInput) -> proc_macro2::TokenStream {
 ///          current_dir: None,
 ///      }
 ///  }
-fn generate_builder_method(input: &DeriveInput, builder_ident: &Ident) -> proc_macro2::TokenStream {
-    let optional_field_stream = parse_optional_fields(input, true);
-    if let Err(err) = optional_field_stream {
-        return err.into_compile_error();
-    }
-    let optional_field_stream = optional_field_stream.unwrap();
+fn generate_builder_method(target: &Target) -> proc_macro2::TokenStream {
+    let core = &target.core;
+    let builder_ident = &target.builder_ident;
+    let method_ident = &target.method_ident;
+    let optional_field_stream = parse_optional_fields(target, true);
+    let (_, ty_generics, _) = target.input.generics.split_for_impl();
+    let method_vis = builder_method_vis(target);
+    let doc = doc_attr(format!("Creates a builder of [`{}`] with no field set.", target_name(target)));
+    let fn_token = if let Ok(true) = parse_struct_attr_flag(target.input, "builder", "const") {
+        quote!(const fn)
+    } else {
+        quote!(fn)
+    };
     quote! {
-        pub fn builder() -> #builder_ident {
+        #doc
+        #method_vis #fn_token #method_ident() -> #builder_ident #ty_generics {
             #builder_ident{
-                #(#optional_field_stream),*
+                #(#optional_field_stream,)*
+                __phantom: #core::marker::PhantomData,
             }
         }
     }
@@ -95,103 +557,974 @@ fn generate_builder_method(input: &DeriveInput, builder_ident: &Ident) -> proc_m
 ///     self
 /// }
 /// ...
-fn generate_setters(input: &DeriveInput) -> syn::Result<Vec<proc_macro2::TokenStream>> {
-    parse_fields(input, |field| {
-        let vis = &field.vis;
+///
+/// The receiver and return type follow the pattern of the builder:
+/// owned:     fn executable(mut self, executable: String) -> Self
+/// immutable: fn executable(&self, executable: String) -> Self
+///
+/// If typestate is true, setters take and return the builder by value, and
+/// setters of required fields mark the field as set in the builder type:
+///
+/// fn executable(self, executable: String) -> CommandBuilder<CommandBuilderSet> {
+///     CommandBuilder {
+///         executable: Some(executable),
+///         ...
+///     }
+/// }
+///
+/// Fields with the sub_builder attribute get the builder of their type:
+///
+/// fn limits(&mut self) -> &mut LimitsBuilder {
+///     self.limits.get_or_insert_with(<Limits>::builder)
+/// }
+///
+/// Option fields can also be set from an Option and be cleared again. With
+/// #[builder(setter(strip_option = false))] their setter takes the Option:
+///
+/// fn maybe_current_dir(&mut self, current_dir: Option<String>) -> &mut Self
+/// fn unset_current_dir(&mut self) -> &mut Self
+///
+/// If constant is true, setters are const fn. They can neither drop the
+/// previous value of the field nor the builder, so they read its fields out of
+/// a ManuallyDrop and forget the previous value:
+///
+/// const fn executable(self, executable: String) -> Self {
+///     let __value = Some(executable);
+///     let __this = ...;
+///     unsafe {
+///         core::mem::forget(core::ptr::read(&(*__this).executable));
+///         Self { executable: __value, args: core::ptr::read(&(*__this).args), ... }
+///     }
+/// }
+fn generate_setters(
+    target: &Target,
+    typestate: bool,
+    pattern: Pattern,
+    constant: bool,
+) -> Vec<proc_macro2::TokenStream> {
+    let core = &target.core;
+    let input = target.input;
+    let builder_ident = &target.builder_ident;
+    let (set_ident, _) = typestate_marker_idents(builder_ident);
+    let params = typestate_params(target);
+    let args = generic_args(&input.generics);
+    let idents = parse_fields(target, |field| field.ident.clone());
+    // The builder to modify is bound to this, immutable setters clone it first.
+    let (receiver, ret, this, init) = match pattern {
+        Pattern::Owned => (quote!(mut self), quote!(Self), quote!(self), quote!()),
+        Pattern::Mutable => (quote!(&mut self), quote!(&mut Self), quote!(self), quote!()),
+        Pattern::Immutable => (
+            quote!(&self),
+            quote!(Self),
+            quote!(__builder),
+            quote!(let mut __builder = #core::clone::Clone::clone(self);),
+        ),
+    };
+    parse_fields(target, |field| {
+        let vis = match parse_setter_vis(input, field) {
+            Ok(vis) => vis,
+            Err(err) => return err.into_compile_error(),
+        };
         let ident = &field.ident;
-        let ty = if let Some(ty) = parse_generic_type(field, "Option") {
-            ty
-        } else { field.ty.clone() };
+        let strip_option = match is_setter_strip_option(input, field) {
+            Ok(strip_option) => strip_option,
+            Err(err) => return err.into_compile_error(),
+        };
+        let option_ty = parse_generic_type(field, "Option");
+        let ty = match &option_ty {
+            Some(ty) if strip_option => ty.clone(),
+            _ => field.ty.clone(),
+        };
         let attr = parse_field_attr_val(field, "builder", "each");
         if let Err(err) = attr {
             return err.into_compile_error();
         }
+        if let Some(meta) = parse_field_attr(field, "builder", "skip").ok().flatten() {
+            return Error::new_spanned(meta, "expected `builder(skip)`").into_compile_error();
+        }
+        let try_setter = match parse_field_attr(field, "builder", "try_setter") {
+            Ok(Some(syn::Meta::Path(_))) => true,
+            Ok(Some(meta)) => return Error::new_spanned(meta, "expected `builder(try_setter)`").into_compile_error(),
+            Ok(None) => false,
+            Err(err) => return err.into_compile_error(),
+        };
+        if try_setter && (is_sub_builder(field) || matches!(attr, Ok(Some(_)))) {
+            return Error::new_spanned(field, "`try_setter` is not supported on `each` and `sub_builder` fields").into_compile_error();
+        }
+        if let Err(err) = parse_field_name(field) {
+            return err.into_compile_error();
+        }
+        let field_str = ident.as_ref().unwrap().to_string();
+        if is_sub_builder(field) {
+            let sub_builder_ty = match parse_sub_builder_type(field) {
+                Ok(sub_builder_ty) => sub_builder_ty,
+                Err(err) => return err.into_compile_error(),
+            };
+            let ty = &field.ty;
+            let docs = field_docs(field, format!("Builder of `{}`.", field_str));
+            return quote! {
+                #docs
+                #vis fn #ident (&mut self) -> &mut #sub_builder_ty {
+                    self.#ident.get_or_insert_with(<#ty>::builder)
+                }
+            };
+        }
+        let into = match is_setter_into(input, field) {
+            Ok(into) => into,
+            Err(err) => return err.into_compile_error(),
+        };
+        let arg_ty = |ty: &syn::Type| if into {
+            quote!(impl #core::convert::Into<#ty>)
+        } else {
+            quote!(#ty)
+        };
+        let arg_value = |arg: &Ident| if into {
+            quote!(#arg.into())
+        } else {
+            quote!(#arg)
+        };
+        let value = arg_value(ident.as_ref().unwrap());
         let mut stream = quote!();
         if let Some(val) = attr.unwrap() {
             let name = Ident::new(&val, field.span());
-            let ty = parse_generic_type(field, "Vec");
-            if ty.is_none() {
-                return syn::Error::new_spanned(field, "field type must be Vec<?>").into_compile_error();
-            }
-            let ty = ty.unwrap();
+            // Maps take the key and the value as separate arguments.
+            let item_types = parse_each_item_types(field, core);
+            let (params, item, items_ty, items) = match item_types.as_slice() {
+                [key_ty, value_ty] => {
+                    let (key_ty, value_ty) = (arg_ty(key_ty), arg_ty(value_ty));
+                    let key = Ident::new("key", field.span());
+                    let value = Ident::new("value", field.span());
+                    let (key_item, value_item) = (arg_value(&key), arg_value(&value));
+                    let items = if into {
+                        quote!(#core::iter::Iterator::map(#core::iter::IntoIterator::into_iter(#ident), |(#key, #value)| (#key_item, #value_item)))
+                    } else {
+                        quote!(#ident)
+                    };
+                    (quote!(#key: #key_ty, #value: #value_ty), quote!((#key_item, #value_item)), quote!((#key_ty, #value_ty)), items)
+                },
+                _ => {
+                    let item_ty = arg_ty(&item_types[0]);
+                    let items = if into {
+                        quote!(#core::iter::Iterator::map(#core::iter::IntoIterator::into_iter(#ident), #core::convert::Into::into))
+                    } else {
+                        quote!(#ident)
+                    };
+                    (quote!(#ident: #item_ty), value.clone(), item_ty, items)
+                },
+            };
+            let docs = field_docs(field, format!("Adds an item to `{}`.", field_str));
             stream.extend([
                 quote! {
-                    #vis fn #name (&mut self, #ident: #ty) -> &mut Self {
-                        if self.#ident.is_none() {
-                            self.#ident = Some(vec![]);
-                        }
-                        self.#ident.as_mut().unwrap().push(#ident);
-                        self
+                    #docs
+                    #vis fn #name (#receiver, #params) -> #ret {
+                        #init
+                        #core::iter::Extend::extend(
+                            #this.#ident.get_or_insert_with(#core::default::Default::default),
+                            #core::iter::once(#item),
+                        );
+                        #this
                     }
                 }
             ]);
+            let extend_ident = method_ident("extend_", ident.as_ref().unwrap(), "");
+            let clear_ident = method_ident("clear_", ident.as_ref().unwrap(), "");
+            let extend_doc = doc_attr(format!("Adds the items to `{}`.", field_str));
+            let clear_doc = doc_attr(format!("Removes every item of `{}`.", field_str));
+            stream.extend([quote! {
+                #extend_doc
+                #vis fn #extend_ident (#receiver, #ident: impl #core::iter::IntoIterator<Item = #items_ty>) -> #ret {
+                    #init
+                    #core::iter::Extend::extend(
+                        #this.#ident.get_or_insert_with(#core::default::Default::default),
+                        #items,
+                    );
+                    #this
+                }
+
+                #clear_doc
+                #vis fn #clear_ident (#receiver) -> #ret {
+                    #init
+                    #this.#ident = Some(#core::default::Default::default());
+                    #this
+                }
+            }]);
+            // The setter of the whole collection is kept unless the each setter takes its name.
+            if name != field_str {
+                let setter_ty = arg_ty(&field.ty);
+                let docs = field_docs(field, format!("Sets `{}`.", field_str));
+                stream.extend([quote! {
+                    #docs
+                    #vis fn #ident (#receiver, #ident: #setter_ty) -> #ret {
+                        #init
+                        #this.#ident = Some(#value);
+                        #this
+                    }
+                }]);
+            }
+        } else if let (true, Some(own_param)) = (typestate, typestate_param(field)) {
+            // Only the parameter of this field changes, the others are kept.
+            let out_params = params.iter().flatten().map(|param| {
+                if *param == own_param {
+                    quote!(#set_ident)
+                } else {
+                    quote!(#param)
+                }
+            });
+            let others = idents.iter().filter(|other| *other != ident);
+            let arg_ty = arg_ty(&ty);
+            let out_ty = quote!(#builder_ident<#(#args,)* #(#out_params),*>);
+            let docs = field_docs(field, format!("Sets `{}`.", field_str));
+            stream.extend([quote! {
+                #docs
+                #vis fn #ident (self, #ident: #arg_ty) -> #out_ty {
+                    #builder_ident {
+                        #ident: Some(#value),
+                        #(#others: self.#others,)*
+                        __phantom: #core::marker::PhantomData,
+                    }
+                }
+            }]);
+            if try_setter {
+                stream.extend([generate_try_setter(&vis, ident, &ty, quote!(self), out_ty, core)]);
+            }
         } else {
+            let (fn_token, receiver) = if constant {
+                (quote!(const fn), quote!(self))
+            } else {
+                (quote!(fn), receiver.clone())
+            };
+            let assign = |stored: proc_macro2::TokenStream| if constant {
+                let others: Vec<_> = idents.iter().filter(|other| *other != ident).collect();
+                let this_stream = const_builder_ptr(core);
+                quote! {
+                    let __value = #stored;
+                    #this_stream
+                    // SAFETY: each field is read once from the builder, which is never dropped.
+                    unsafe {
+                        #core::mem::forget(#core::ptr::read(&(*__this).#ident));
+                        Self {
+                            #ident: __value,
+                            #(#others: #core::ptr::read(&(*__this).#others),)*
+                            __phantom: #core::marker::PhantomData,
+                        }
+                    }
+                }
+            } else {
+                quote! {
+                    #init
+                    #this.#ident = #stored;
+                    #this
+                }
+            };
+            let setter_ty = arg_ty(&ty);
+            // Without strip_option the value is already an Option.
+            let stored = if option_ty.is_some() && !strip_option {
+                value.clone()
+            } else {
+                quote!(Some(#value))
+            };
+            let docs = field_docs(field, format!("Sets `{}`.", field_str));
+            let set_stream = assign(stored);
             stream.extend([quote! {
-                #vis fn #ident (&mut self, #ident: #ty) -> &mut Self {
-                    self.#ident = Some(#ident);
-                    self
+                #docs
+                #vis #fn_token #ident (#receiver, #ident: #setter_ty) -> #ret {
+                    #set_stream
                 }
             }]);
+            if let Some(item_ty) = &option_ty {
+                let field_ident = ident.as_ref().unwrap();
+                let maybe_ident = method_ident("maybe_", field_ident, "");
+                let unset_ident = method_ident("unset_", field_ident, "");
+                let maybe_value = if into {
+                    quote!(#ident.map(#core::convert::Into::into))
+                } else {
+                    quote!(#ident)
+                };
+                let item_ty = arg_ty(item_ty);
+                let maybe_doc = doc_attr(format!("Sets `{}` to the value if it is some, clears it otherwise.", field_str));
+                let unset_doc = doc_attr(format!("Clears `{}`.", field_str));
+                if strip_option {
+                    let maybe_stream = assign(maybe_value);
+                    stream.extend([quote! {
+                        #maybe_doc
+                        #vis #fn_token #maybe_ident (#receiver, #ident: #core::option::Option<#item_ty>) -> #ret {
+                            #maybe_stream
+                        }
+                    }]);
+                }
+                let unset_stream = assign(quote!(None));
+                stream.extend([quote! {
+                    #unset_doc
+                    #vis #fn_token #unset_ident (#receiver) -> #ret {
+                        #unset_stream
+                    }
+                }]);
+            }
+            if try_setter {
+                // The setter is called on the receiver, owned builders need not be mutable.
+                let try_receiver = match pattern {
+                    Pattern::Owned => quote!(self),
+                    _ => receiver,
+                };
+                stream.extend([generate_try_setter(&vis, ident, &ty, try_receiver, ret.clone(), core)]);
+            }
         }
         stream
     })
 }
 
+/// Generate the fallible setter of a field with the try_setter attribute,
+/// converting the value with TryInto before passing it to the setter.
+/// fn try_port<__V: TryInto<u16>>(&mut self, port: __V) -> Result<&mut Self, __V::Error> {
+///     let port = TryInto::try_into(port)?;
+///     Ok(self.port(port))
+/// }
+fn generate_try_setter(
+    vis: &syn::Visibility,
+    ident: &Option<Ident>,
+    ty: &syn::Type,
+    receiver: proc_macro2::TokenStream,
+    ret: proc_macro2::TokenStream,
+    core: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let ident = ident.as_ref().unwrap();
+    let try_ident = method_ident("try_", ident, "");
+    let doc = doc_attr(format!("Sets `{}` to the value converted with TryInto, or returns the error of the conversion.", ident));
+    quote! {
+        #doc
+        #vis fn #try_ident<__V: #core::convert::TryInto<#ty>>(
+            #receiver,
+            #ident: __V,
+        ) -> #core::result::Result<#ret, <__V as #core::convert::TryInto<#ty>>::Error> {
+            let #ident = #core::convert::TryInto::try_into(#ident)?;
+            Ok(self.#ident(#ident))
+        }
+    }
+}
+
 /// Generate build method.
-/// pub fn build(&mut self) -> Result<Command, Box<dyn Error>> {
+/// pub fn build(&mut self) -> Result<Command, CommandBuilderError> {
 //     ...
 //  }
-fn generate_build_method(input: &DeriveInput) -> proc_macro2::TokenStream {
-    let struct_ident = &input.ident;
-    let check_none_stream = parse_fields(input, |field| {
-        let ident = &field.ident;
-        let ident_str = ident.as_ref().unwrap().to_string();
-        if let Some(_ty) = parse_generic_type(field, "Option") {
-            return quote!();
-        }
-        if let Ok(Some(_)) = parse_field_attr_val(field, "builder", "each") {
-            return quote!();
-        }
-        quote! {
-            if self.#ident.is_none() {
-                return Err(std::boxed::Box::new(BuildError(format!("{} is none", #ident_str))))
-            }
-        }
-    });
-    if let Err(err) = check_none_stream {
+///
+/// If typestate is true, build is only implemented once every required
+/// field is set, so it can neither fail nor leave the builder empty:
+/// pub fn build(self) -> Command {
+//     ...
+//  }
+///
+/// The receiver follows the pattern of the builder, owned builders are
+/// consumed and immutable builders clone the fields:
+/// pub fn build(self) -> Result<Command, CommandBuilderError>
+/// pub fn build(&self) -> Result<Command, CommandBuilderError>
+///
+/// Fields with the sub_builder attribute are built first, the fields missing
+/// in their builders are reported with the path of the field, limits.max_mem.
+///
+/// Mutable builders take the fields of their sub builders when building them,
+/// so they first check that the build cannot fail, without modifying any
+/// builder, and a failed build leaves the builder as it was. Sub builders of
+/// any pattern are then taken out and built:
+/// if let Err(err) = self.__check_build() {
+///     return Err(err);
+/// }
+/// let __limits = match self.limits.take().unwrap_or_else(<Limits>::builder).build() { ... }
+///
+/// With #[builder(build_fn(validate = "path"))], the builder is passed to
+/// path before the struct is constructed, and build returns its error:
+/// if let Err(err) = path(self) {
+///     return Err(CommandBuilderError::ValidationError(err.into()));
+/// }
+///
+/// If constant is true, build is a const fn panicking when a required field is
+/// not set, which fails the compilation when it is evaluated in a constant.
+/// The fields are read out of the builder like in const setters:
+/// pub const fn build(self) -> Command {
+///     let executable = unsafe { core::ptr::read(&(*__this).executable) };
+///     ...
+///     Command {
+///         executable: if executable.is_some() {
+///             executable.unwrap()
+///         } else {
+///             core::mem::forget(executable);
+///             panic!("`executable` must be initialized")
+///         },
+///         ...
+///     }
+/// }
+fn generate_build_method(
+    target: &Target,
+    typestate: bool,
+    pattern: Pattern,
+    constant: bool,
+) -> proc_macro2::TokenStream {
+    let (core, alloc) = (&target.core, &target.alloc);
+    let input = target.input;
+    let path = &target.path;
+    let target_ty = target_type(input);
+    let error_ident = error_ident(&target.builder_ident);
+    let members = target_members(target);
+    // Report invalid default values here rather than inside the struct expression.
+    if let Some(Err(err)) = target.fields.iter().map(|field| parse_field_default(field, core)).find(|default| default.is_err()) {
         return err.into_compile_error();
     }
-    let check_none_stream = check_none_stream.unwrap();
-
-    // There will be no error here if the above runs successfully.
-    let assignment_stream = parse_fields(input, |field| {
-        let ident = &field.ident;
-        if let Some(_ty) = parse_generic_type(field, "Option") {
-            return quote! {
-                #ident: self.#ident.take()
+    let skipped_stream = generate_skipped_values(target);
+    let doc = doc_attr(format!("Builds a [`{}`] from the fields set in the builder.", target_name(target)));
+    // There will be no error here if the builder struct is generated.
+    let validate_stream = |builder: proc_macro2::TokenStream| match parse_build_fn_validate(input) {
+        Ok(Some(validate)) => quote! {
+            if let Err(err) = #validate(#builder) {
+                return Err(#error_ident::ValidationError(#core::convert::Into::into(err)));
+            }
+        },
+        _ => quote!(),
+    };
+    if constant {
+        let idents = parse_fields(target, |field| field.ident.clone());
+        // Option::unwrap_or_else is not const, and a matched Option may be
+        // dropped, which const fn cannot do for fields with a destructor. Each
+        // Option is moved out in both branches instead.
+        let value_stream = parse_fields(target, |field| {
+            let ident = &field.ident;
+            let ident_str = ident.as_ref().unwrap().to_string();
+            let default = parse_field_default(field, core).ok().flatten();
+            let (some, none) = match (parse_generic_type(field, "Option"), default) {
+                (Some(_), Some(default)) => (quote!(#ident), default),
+                (Some(_), None) => return quote!(#ident),
+                (None, Some(default)) => (quote!(#core::option::Option::unwrap(#ident)), default),
+                (None, None) => {
+                    let message = format!("`{}` must be initialized", ident_str);
+                    (quote!(#core::option::Option::unwrap(#ident)), quote!(#core::panic!(#message)))
+                },
             };
-        }
-        if let Ok(Some(_)) = parse_field_attr_val(field, "builder", "each") {
+            quote! {
+                if #ident.is_some() {
+                    #some
+                } else {
+                    #core::mem::forget(#ident);
+                    #none
+                }
+            }
+        });
+        let this_stream = const_builder_ptr(core);
+        return quote! {
+            #doc
+            pub const fn build(self) -> #target_ty {
+                #this_stream
+                // SAFETY: each field is read once from the builder, which is never dropped.
+                #(let #idents = unsafe { #core::ptr::read(&(*__this).#idents) };)*
+                #path {
+                    #(#members: #value_stream,)*
+                    #(#skipped_stream,)*
+                }
+            }
+        };
+    }
+    if typestate {
+        let value_stream = parse_fields(target, |field| {
+            let ident = &field.ident;
+            generate_field_value(field, quote!(self.#ident), core)
+        });
+        if let Ok(Some(_)) = parse_build_fn_validate(input) {
+            let validate_stream = validate_stream(quote!(&self));
             return quote! {
-                #ident: self.#ident.take().unwrap_or_default()
-            };
-        }
-        quote! {
-            #ident: self.#ident.take().unwrap()
-        }
-    }).unwrap();
-    
-    quote! {
-        pub fn build(&mut self) -> std::result::Result<#struct_ident, std::boxed::Box<dyn std::error::Error>> {
-            #(#check_none_stream)*
+                #doc
+                pub fn build(self) -> #core::result::Result<#target_ty, #error_ident> {
+                    #validate_stream
 
-            Ok(#struct_ident {
-                #(#assignment_stream),*
-            })
+                    Ok(#path {
+                        #(#members: #value_stream,)*
+                        #(#skipped_stream,)*
+                    })
+                }
+            };
+        }
+        return quote! {
+            #doc
+            pub fn build(self) -> #target_ty {
+                #path {
+                    #(#members: #value_stream,)*
+                    #(#skipped_stream,)*
+                }
+            }
+        };
+    }
+    let check_none_stream = generate_missing_field_checks(target);
+
+    // Sub builders are taken out of owned builders, so they need to be mutable.
+    let has_sub_builder = parse_fields(target, is_sub_builder).into_iter().any(|sub_builder| sub_builder);
+    let (receiver, builder) = match pattern {
+        Pattern::Owned if has_sub_builder => (quote!(mut self), quote!(&self)),
+        Pattern::Owned => (quote!(self), quote!(&self)),
+        Pattern::Mutable => (quote!(&mut self), quote!(self)),
+        Pattern::Immutable => (quote!(&self), quote!(self)),
+    };
+
+    let sub_builder_stream = parse_fields(target, |field| {
+        if !is_sub_builder(field) {
+            return quote!();
+        }
+        let ident = &field.ident;
+        let ident_str = ident.as_ref().unwrap().to_string();
+        let local = sub_builder_local(field);
+        let ty = &field.ty;
+        let sub_builder = match pattern {
+            // Checked by __check_build, the build cannot report missing fields.
+            Pattern::Mutable => return quote! {
+                let #local = match self.#ident.take().unwrap_or_else(<#ty>::builder).build() {
+                    Ok(value) => Some(value),
+                    Err(err) => {
+                        return Err(#error_ident::ValidationError(#alloc::format!("{}: {}", #ident_str, err)));
+                    },
+                };
+            },
+            Pattern::Owned => quote!(self.#ident.take().unwrap_or_else(<#ty>::builder)),
+            Pattern::Immutable => quote!(#core::clone::Clone::clone(&self.#ident).unwrap_or_else(<#ty>::builder)),
+        };
+        quote! {
+            let #local = match #sub_builder.build() {
+                Ok(value) => Some(value),
+                Err(err) if !err.missing_fields().is_empty() => {
+                    for field in err.missing_fields() {
+                        missing_fields.push(#alloc::format!("{}.{}", #ident_str, field));
+                    }
+                    None
+                },
+                Err(err) => {
+                    return Err(#error_ident::ValidationError(#alloc::format!("{}: {}", #ident_str, err)));
+                },
+            };
+        }
+    });
+
+    let value_stream = parse_fields(target, |field| {
+        let ident = &field.ident;
+        if is_sub_builder(field) {
+            let local = sub_builder_local(field);
+            return quote!(#local.unwrap());
+        }
+        let value = match pattern {
+            Pattern::Owned => quote!(self.#ident),
+            Pattern::Mutable => quote!(self.#ident.take()),
+            Pattern::Immutable => quote!(#core::clone::Clone::clone(&self.#ident)),
+        };
+        generate_field_value(field, value, core)
+    });
+    let check_validate_stream = validate_stream(quote!(self));
+    let validate_stream = validate_stream(builder);
+
+    // Every builder can check itself, sub builders of mutable builders are
+    // checked before they are taken.
+    let sub_builder_check_stream = parse_fields(target, |field| {
+        if !is_sub_builder(field) {
+            return quote!();
+        }
+        let ident = &field.ident;
+        let ident_str = ident.as_ref().unwrap().to_string();
+        let local = sub_builder_local(field);
+        let ty = &field.ty;
+        quote! {
+            let #local = match &self.#ident {
+                Some(builder) => builder.__check_build(),
+                None => <#ty>::builder().__check_build(),
+            };
+            if let Err(err) = &#local {
+                for field in err.missing_fields() {
+                    missing_fields.push(#alloc::format!("{}.{}", #ident_str, field));
+                }
+            }
+        }
+    });
+    let sub_builder_error_stream = parse_fields(target, |field| {
+        if !is_sub_builder(field) {
+            return quote!();
+        }
+        let ident_str = field.ident.as_ref().unwrap().to_string();
+        let local = sub_builder_local(field);
+        quote! {
+            if let Err(err) = #local {
+                return Err(#error_ident::ValidationError(#alloc::format!("{}: {}", #ident_str, err)));
+            }
+        }
+    });
+    let check_method_stream = quote! {
+        #[doc(hidden)]
+        pub fn __check_build(&self) -> #core::result::Result<(), #error_ident> {
+            #[allow(unused_mut)]
+            let mut missing_fields = #alloc::vec::Vec::new();
+            #(#check_none_stream)*
+            #(#sub_builder_check_stream)*
+            if !missing_fields.is_empty() {
+                return Err(#error_ident::MissingFields(missing_fields));
+            }
+            #check_validate_stream
+            #(#sub_builder_error_stream)*
+            Ok(())
+        }
+    };
+
+    if let Pattern::Mutable = pattern {
+        return quote! {
+            #doc
+            pub fn build(#receiver) -> #core::result::Result<#target_ty, #error_ident> {
+                if let Err(err) = self.__check_build() {
+                    return Err(err);
+                }
+                #(#sub_builder_stream)*
+
+                Ok(#path {
+                    #(#members: #value_stream,)*
+                    #(#skipped_stream,)*
+                })
+            }
+
+            #check_method_stream
+        };
+    }
+
+    quote! {
+        #check_method_stream
+
+        #doc
+        pub fn build(#receiver) -> #core::result::Result<#target_ty, #error_ident> {
+            #[allow(unused_mut)]
+            let mut missing_fields = #alloc::vec::Vec::new();
+            #(#check_none_stream)*
+            #(#sub_builder_stream)*
+            if !missing_fields.is_empty() {
+                return Err(#error_ident::MissingFields(missing_fields));
+            }
+            #validate_stream
+
+            Ok(#path {
+                #(#members: #value_stream,)*
+                #(#skipped_stream,)*
+            })
+        }
+    }
+}
+
+/// Generate the methods reading the fields of the builder from the environment
+/// or from a map, one variable per field named after the prefix and the field.
+/// pub fn from_env() -> Result<CommandBuilder, CommandBuilderError> {
+///     Self::from_map(&vars)
+/// }
+/// pub fn from_map(map: &HashMap<String, String>) -> Result<CommandBuilder, CommandBuilderError> {
+///     let mut builder = CommandBuilder { executable: None, ... };
+///     if let Some(item) = map.get("APP_EXECUTABLE") {
+///         builder.executable = Some(FromStr::from_str(item)?);
+///     }
+///     if let Some(value) = map.get("APP_ARGS") {
+///         for item in value.split(",") { ... }
+///     }
+///     ...
+///     Ok(builder)
+/// }
+fn generate_from_env_methods(target: &Target, from_env: &FromEnv) -> proc_macro2::TokenStream {
+    let (core, alloc) = (&target.core, &target.alloc);
+    let error_ident = error_ident(&target.builder_ident);
+    let optional_field_stream = parse_optional_fields(target, true);
+    let separator = &from_env.separator;
+    let field_stream = parse_fields(target, |field| {
+        let ident = field.ident.as_ref().unwrap();
+        let name = ident.to_string();
+        let var = format!("{}{}", from_env.prefix, name.to_uppercase());
+        let invalid_value = quote! {
+            #error_ident::InvalidValue {
+                field: #alloc::string::String::from(#name),
+                value: #alloc::string::String::from(item),
+                message: #alloc::string::ToString::to_string(&err),
+            }
+        };
+        if is_sub_builder(field) {
+            return Error::new_spanned(field, "`from_env` does not support `sub_builder` fields").into_compile_error();
+        }
+        if let Ok(Some(_)) = parse_field_attr_val(field, "builder", "each") {
+            let item_ty = match parse_each_item_types(field, core) {
+                item_types if item_types.len() == 1 => item_types.into_iter().next().unwrap(),
+                _ => return Error::new_spanned(&field.ty, "`from_env` only supports collections of one item type such as Vec<T>").into_compile_error(),
+            };
+            return quote! {
+                if let Some(value) = map.get(#var) {
+                    let items = builder.#ident.get_or_insert_with(#core::default::Default::default);
+                    for item in value.split(#separator).filter(|item| !item.is_empty()) {
+                        match <#item_ty as #core::str::FromStr>::from_str(item) {
+                            Ok(parsed) => #core::iter::Extend::extend(items, #core::iter::once(parsed)),
+                            Err(err) => return Err(#invalid_value),
+                        }
+                    }
+                }
+            };
+        }
+        let ty = parse_generic_type(field, "Option").unwrap_or_else(|| field.ty.clone());
+        quote! {
+            if let Some(item) = map.get(#var) {
+                match <#ty as #core::str::FromStr>::from_str(item) {
+                    Ok(parsed) => builder.#ident = Some(parsed),
+                    Err(err) => return Err(#invalid_value),
+                }
+            }
+        }
+    });
+    let var_doc = doc_attr(format!(
+        "Creates a builder with the fields set from the variables named `{}` followed by the field in uppercase.",
+        from_env.prefix,
+    ));
+    quote! {
+        /// Creates a builder with the fields set from the environment.
+        ///
+        #var_doc
+        pub fn from_env() -> #core::result::Result<Self, #error_ident> {
+            // Variables that are not unicode cannot name or hold a field.
+            let vars: std::collections::HashMap<#alloc::string::String, #alloc::string::String> = std::env::vars_os()
+                .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+                .collect();
+            Self::from_map(&vars)
+        }
+
+        /// Creates a builder with the fields set from the variables in map.
+        pub fn from_map(
+            map: &std::collections::HashMap<#alloc::string::String, #alloc::string::String>,
+        ) -> #core::result::Result<Self, #error_ident> {
+            let mut builder = Self {
+                #(#optional_field_stream,)*
+                __phantom: #core::marker::PhantomData,
+            };
+            #(#field_stream)*
+            Ok(builder)
         }
     }
 }
 
+/// Generate the methods parsing the fields of the builder from command-line
+/// arguments, one flag per setter. Flags of each fields can be repeated, bool
+/// fields are set to true by a flag without value, and to false without their
+/// flag unless they have a default.
+/// pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<CommandBuilder, CommandBuilderError> {
+///     let mut builder = CommandBuilder { executable: None, ... };
+///     let mut args = args.into_iter();
+///     while let Some(arg) = args.next() {
+///         let (flag, value) = ...;
+///         match flag {
+///             "--help" => return Err(CommandBuilderError::Help(...)),
+///             "--executable" => {
+///                 let item = ...;
+///                 builder.executable = Some(FromStr::from_str(&item)?);
+///             },
+///             "--arg" => { ... },
+///             ...
+///             _ => return Err(CommandBuilderError::UnknownArgument(arg)),
+///         }
+///     }
+///     Ok(builder)
+/// }
+/// pub fn help() -> &'static str
+fn generate_cli_methods(target: &Target) -> proc_macro2::TokenStream {
+    let (core, alloc) = (&target.core, &target.alloc);
+    let error_ident = error_ident(&target.builder_ident);
+    let optional_field_stream = parse_optional_fields(target, true);
+    let flags = parse_fields(target, cli_flag);
+    let mut help_rows = Vec::new();
+    // Bool fields without a default are switches, false unless their flag is given.
+    let mut switches = Vec::new();
+    let arm_stream = parse_fields(target, |field| {
+        let ident = field.ident.as_ref().unwrap();
+        let name = ident.to_string();
+        let flag = cli_flag(field)?;
+        let placeholder = format!("<{}>", flag.trim_start_matches('-').replace('-', "_").to_uppercase());
+        let invalid_value = quote! {
+            #error_ident::InvalidValue {
+                field: #alloc::string::String::from(#name),
+                value: item,
+                message: #alloc::string::ToString::to_string(&err),
+            }
+        };
+        let missing_value = quote! {
+            let item = match value.map(#alloc::string::String::from).or_else(|| args.next()) {
+                Some(item) => item,
+                None => return Err(#error_ident::MissingValue(#alloc::string::String::from(#flag))),
+            };
+        };
+        if is_sub_builder(field) {
+            return Err(Error::new_spanned(field, "`cli` does not support `sub_builder` fields"));
+        }
+        if flags.iter().filter(|other| matches!(other, Ok(other) if *other == flag)).count() > 1 {
+            return Err(Error::new_spanned(field, format!("`cli` flag `{}` is used more than once", flag)));
+        }
+        if let Ok(Some(_)) = parse_field_attr_val(field, "builder", "each") {
+            let item_ty = match parse_each_item_types(field, core) {
+                item_types if item_types.len() == 1 => item_types.into_iter().next().unwrap(),
+                _ => return Err(Error::new_spanned(&field.ty, "`cli` only supports collections of one item type such as Vec<T>")),
+            };
+            help_rows.push((format!("{} {}...", flag, placeholder), cli_help(field, "")));
+            return Ok(quote! {
+                #flag => {
+                    #missing_value
+                    match <#item_ty as #core::str::FromStr>::from_str(&item) {
+                        Ok(parsed) => {
+                            let items = builder.#ident.get_or_insert_with(#core::default::Default::default);
+                            #core::iter::Extend::extend(items, #core::iter::once(parsed));
+                        },
+                        Err(err) => return Err(#invalid_value),
+                    }
+                },
+            });
+        }
+        let option_ty = parse_generic_type(field, "Option");
+        let required = option_ty.is_none() && !matches!(parse_field_attr(field, "builder", "default"), Ok(Some(_)));
+        let ty = option_ty.unwrap_or_else(|| field.ty.clone());
+        let is_bool = matches!(&ty, syn::Type::Path(syn::TypePath { qself: None, path }) if path.is_ident("bool"));
+        let item = if is_bool {
+            // A bool flag without `=` is set and never takes the next
+            // argument, --flag=false unsets it.
+            if required {
+                switches.push(ident.clone());
+            }
+            help_rows.push((flag.clone(), cli_help(field, "")));
+            quote!(let item = #alloc::string::String::from(value.unwrap_or("true"));)
+        } else {
+            help_rows.push((format!("{} {}", flag, placeholder), cli_help(field, if required { " (required)" } else { "" })));
+            missing_value
+        };
+        Ok(quote! {
+            #flag => {
+                #item
+                match <#ty as #core::str::FromStr>::from_str(&item) {
+                    Ok(parsed) => builder.#ident = Some(parsed),
+                    Err(err) => return Err(#invalid_value),
+                }
+            },
+        })
+    });
+    // Errors cannot stand among the match arms.
+    let arm_stream = match arm_stream.into_iter().collect::<syn::Result<Vec<_>>>() {
+        Ok(arm_stream) => arm_stream,
+        Err(err) => return err.into_compile_error(),
+    };
+    help_rows.push(("--help".to_owned(), "Prints this help.".to_owned()));
+    let width = help_rows.iter().map(|(flag, _)| flag.len()).max().unwrap_or(0);
+    let mut help = "Options:\n".to_owned();
+    for (flag, text) in help_rows {
+        help.push_str(format!("  {:width$}  {}", flag, text, width = width).trim_end());
+        help.push('\n');
+    }
+    let help_doc = doc_attr(format!(
+        "`--help` stops the parsing and returns [`{}::Help`] with the text of [`Self::help`], \
+         to be printed before exiting instead of building.",
+        error_ident,
+    ));
+    quote! {
+        /// Creates a builder with the fields set from command-line arguments,
+        /// without the program name, such as `--field value` or `--field=value`.
+        /// Bool fields without a default are false when their flag is absent.
+        ///
+        #help_doc
+        pub fn parse_args<__I: #core::iter::IntoIterator<Item = #alloc::string::String>>(
+            args: __I,
+        ) -> #core::result::Result<Self, #error_ident> {
+            let mut builder = Self {
+                #(#optional_field_stream,)*
+                __phantom: #core::marker::PhantomData,
+            };
+            let mut args = #core::iter::IntoIterator::into_iter(args);
+            while let Some(arg) = args.next() {
+                // The value follows the flag, in the same argument after `=`
+                // or in the next argument.
+                let (flag, value) = match arg.split_once('=') {
+                    Some((flag, value)) if flag.starts_with("--") => (flag, Some(value)),
+                    _ => (arg.as_str(), None),
+                };
+                match flag {
+                    "--help" => return Err(#error_ident::Help(#alloc::string::String::from(Self::help()))),
+                    #(#arm_stream)*
+                    _ => return Err(#error_ident::UnknownArgument(arg)),
+                }
+            }
+            #(
+                if builder.#switches.is_none() {
+                    builder.#switches = Some(false);
+                }
+            )*
+            Ok(builder)
+        }
+
+        /// Help of the command-line arguments read by [`Self::parse_args`].
+        pub fn help() -> &'static str {
+            #help
+        }
+    }
+}
+
+/// Flag of a field on the command line, named after its setter.
+/// args: Vec<String>  => --args
+/// #[builder(each = "arg")] => --arg
+/// current_dir        => --current-dir
+/// _0                 => --0
+fn cli_flag(field: &syn::Field) -> syn::Result<String> {
+    let name = match parse_field_attr_val(field, "builder", "each")? {
+        Some(each) => each,
+        None => field_name(field.ident.as_ref().unwrap()),
+    };
+    Ok(format!("--{}", name.replace('_', "-")))
+}
+
+/// Help of a field on the command line, the first paragraph of its docs.
+fn cli_help(field: &syn::Field, suffix: &str) -> String {
+    let mut lines = Vec::new();
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("doc")) {
+        if let Ok(syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit), ..
+        })) = attr.parse_meta() {
+            let line = lit.value().trim().to_owned();
+            if line.is_empty() && !lines.is_empty() {
+                break;
+            }
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+    }
+    lines.join(" ") + suffix
+}
+
+/// Generate value of a field in build method, value is the Option stored in
+/// the builder.
+/// executable: value.unwrap()
+/// args: value.unwrap_or_default()
+/// current_dir: value
+/// ...
+fn generate_field_value(
+    field: &syn::Field,
+    value: proc_macro2::TokenStream,
+    core: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let default = match parse_field_default(field, core) {
+        Ok(default) => default,
+        Err(err) => return err.into_compile_error(),
+    };
+    if let Some(_ty) = parse_generic_type(field, "Option") {
+        if let Some(default) = default {
+            return quote! {
+                #value.or_else(|| #default)
+            };
+        }
+        return value;
+    }
+    if let Some(default) = default {
+        return quote! {
+            #value.unwrap_or_else(|| #default)
+        };
+    }
+    if let Ok(Some(_)) = parse_field_attr_val(field, "builder", "each") {
+        return quote! {
+            #value.unwrap_or_default()
+        };
+    }
+    quote! {
+        #value.unwrap()
+    }
+}
+
 /// Parse field list.
 /// 
 /// if init is true:
@@ -206,46 +1539,150 @@ fn generate_build_method(input: &DeriveInput) -> proc_macro2::TokenStream {
 //  env: Option<Vec<String>>
 //  current_dir: Option<String>
 fn parse_optional_fields(
-    input: &DeriveInput,
+    target: &Target,
     init: bool
-) -> syn::Result<Vec<proc_macro2::TokenStream>> {
-    parse_fields(input, |field| {
+) -> Vec<proc_macro2::TokenStream> {
+    let core = &target.core;
+    parse_fields(target, |field| {
         let vis = &field.vis;
         let ident = &field.ident;
         let ty = &field.ty;
-        let right = if init {
-            quote!(None)
+        if init {
+            return quote! {
+                #ident: None
+            };
+        }
+        let right = if let Some(_ty) = parse_generic_type(field, "Option") {
+            quote!(#ty)
+        } else if let (true, Ok(sub_builder_ty)) = (is_sub_builder(field), parse_sub_builder_type(field)) {
+            quote!(#core::option::Option<#sub_builder_ty>)
         } else {
-            if let Some(_ty) = parse_generic_type(field, "Option") {
-                quote!(#ty)
-            } else {
-                quote!(std::option::Option<#ty>)
-            }
+            quote!(#core::option::Option<#ty>)
         };
+        // Fields of tuple structs and enum variants need no docs in the source.
+        let docs = field_docs(field, format!("Value of `{}`, if set.", ident.as_ref().unwrap()));
         quote! {
+            #docs
             #vis #ident: #right
         }
     })
 }
 
 /// Parse field of struct. Call f function in iteration.
-fn parse_fields(
-    input: &DeriveInput,
-    f: impl Fn(&syn::Field) -> proc_macro2::TokenStream)
--> syn::Result<Vec<proc_macro2::TokenStream>> {
-    if let syn::Data::Struct(syn::DataStruct{fields, ..}) = &input.data {
-        match fields {
-            syn::Fields::Named(syn::FieldsNamed {named, ..}) => {
-                return Ok(named.iter().map(|field| {
-                    f(field)
-                }).collect());
-            },
-            _ => {
-                return Err(Error::new_spanned(&fields, "unexpected fields"))
+///
+/// Every field passed to f is named after its builder field, that is the name
+/// given by #[builder(name = "...")], the name of the field, or _0, _1, ...
+/// for the fields of tuple structs. Fields of enum variants take the
+/// visibility of the enum. Fields with #[builder(skip)] have no builder field
+/// and are left out.
+fn parse_fields<T>(
+    target: &Target,
+    mut f: impl FnMut(&syn::Field) -> T)
+-> Vec<T> {
+    let is_enum = matches!(target.input.data, syn::Data::Enum(_));
+    target.fields.iter().enumerate().filter(|(_, field)| !is_skipped(field)).map(|(i, field)| {
+        let mut field = field.clone();
+        if let Ok(Some(name)) = parse_field_name(&field) {
+            field.ident = Some(name);
+        } else if field.ident.is_none() {
+            field.ident = Some(Ident::new(&format!("_{}", i), field.span()));
+        }
+        if is_enum {
+            field.vis = target.input.vis.clone();
+        }
+        f(&field)
+    }).collect()
+}
+
+/// Parse the members of the fields used to construct the value in build,
+/// in the order of parse_fields.
+/// struct Command { executable: String, ... } => [executable, ...]
+/// struct Point(f64, f64)                      => [0, 1]
+fn target_members(target: &Target) -> Vec<syn::Member> {
+    target.fields.iter().enumerate().filter(|(_, field)| !is_skipped(field)).map(|(i, field)| {
+        field_member(i, field)
+    }).collect()
+}
+
+/// Member of the i-th field of a struct.
+fn field_member(i: usize, field: &syn::Field) -> syn::Member {
+    match &field.ident {
+        Some(ident) => syn::Member::Named(ident.clone()),
+        None => syn::Member::Unnamed(syn::Index { index: i as u32, span: field.span() }),
+    }
+}
+
+/// Generate the values of the fields with #[builder(skip)] in build, their
+/// default value or Default::default().
+/// cache: Default::default()
+fn generate_skipped_values(target: &Target) -> Vec<proc_macro2::TokenStream> {
+    let core = &target.core;
+    target.fields.iter().enumerate().filter(|(_, field)| is_skipped(field)).map(|(i, field)| {
+        let member = field_member(i, field);
+        let default = match parse_field_default(field, core) {
+            Ok(Some(default)) => default,
+            _ => quote!(#core::default::Default::default()),
+        };
+        quote! {
+            #member: #default
+        }
+    }).collect()
+}
+
+/// Path of the value constructed by build, as written in docs.
+/// Command, Shape::Circle
+fn target_name(target: &Target) -> String {
+    target.path.to_string().replace(' ', "")
+}
+
+/// Doc attribute with the given text.
+fn doc_attr(text: String) -> proc_macro2::TokenStream {
+    let text = format!(" {}", text);
+    quote!(#[doc = #text])
+}
+
+/// Doc attributes of the field, forwarded to its builder field and setters.
+/// Fields without docs get the default text unless it is empty.
+fn field_docs(field: &syn::Field, default: String) -> proc_macro2::TokenStream {
+    let docs: Vec<&syn::Attribute> = field.attrs.iter().filter(|attr| attr.path.is_ident("doc")).collect();
+    if docs.is_empty() && !default.is_empty() {
+        return doc_attr(default);
+    }
+    quote!(#(#docs)*)
+}
+
+/// Type of the struct or enum with its generics.
+/// Request<'a, B>
+fn target_type(input: &DeriveInput) -> proc_macro2::TokenStream {
+    let ident = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    quote!(#ident #ty_generics)
+}
+
+/// Consecutive capitals form one word, the last of them starting the next.
+/// HttpRequest => http_request
+/// HTTPRequest => http_request
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev = if i > 0 { Some(chars[i - 1]) } else { None };
+            let next = chars.get(i + 1);
+            let starts_word = match prev {
+                Some(prev) if prev.is_uppercase() => next.is_some_and(|next| next.is_lowercase()),
+                Some(_) => true,
+                None => false,
+            };
+            if starts_word {
+                snake.push('_');
             }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
         }
-    };
-    Err(Error::new_spanned(&input, "unexpected derive input"))
+    }
+    snake
 }
 
 /// Parse generic type of field.
@@ -282,63 +1719,1040 @@ fn parse_generic_type(field: &syn::Field, ty: &str) -> Option<syn::Type> {
     None
 }
 
+/// Parse the item types of a collection field with the each attribute, a
+/// collection is any Default + Extend type whose items are those it iterates
+/// over. Maps, whose name ends with Map, take the key and the value apart.
+/// Vec<String>              => [<Vec<String> as IntoIterator>::Item]
+/// BTreeMap<String, String> => [String, String]
+fn parse_each_item_types(field: &syn::Field, core: &proc_macro2::TokenStream) -> Vec<syn::Type> {
+    if let syn::Type::Path(syn::TypePath { path, .. }) = &field.ty {
+        if let Some(syn::PathSegment {
+            ident,
+            arguments: syn::PathArguments::AngleBracketed(
+                syn::AngleBracketedGenericArguments { args, .. }
+            ),
+        }) = path.segments.last() {
+            let types: Vec<syn::Type> = args.iter().filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty.clone()),
+                _ => None,
+            }).collect();
+            if ident.to_string().ends_with("Map") && types.len() >= 2 {
+                return types[..2].to_vec();
+            }
+        }
+    }
+    let ty = &field.ty;
+    vec![syn::parse_quote!(<#ty as #core::iter::IntoIterator>::Item)]
+}
+
+/// Names allowed in the builder attribute on a field.
+const FIELD_META_NAMES: &[&str] = &["each", "default", "setter", "name", "sub_builder", "skip", "try_setter", "merge"];
+
+/// Names allowed in the builder attribute on the struct.
+const STRUCT_META_NAMES: &[&str] = &[
+    "typestate", "setter", "build_fn", "pattern", "from_env", "name", "vis", "derive", "no_std", "const",
+    "cli",
+];
+
+/// Names allowed in the setter attribute.
+const SETTER_META_NAMES: &[&str] = &["into", "vis", "strip_option"];
+
+/// Names allowed in the build_fn attribute.
+const BUILD_FN_META_NAMES: &[&str] = &["validate"];
+
+/// Names allowed in the from_env attribute.
+const FROM_ENV_META_NAMES: &[&str] = &["prefix", "separator"];
+
+/// Names allowed in the no_std attribute.
+const NO_STD_META_NAMES: &[&str] = &["core_error"];
+
+/// Check every builder attribute of the input before generating anything, so
+/// that all the malformed attributes are reported at once. The values of the
+/// attributes on the struct, or on a field, are checked when their names are
+/// known.
+fn check_attrs(input: &DeriveInput) -> syn::Result<()> {
+    let fields: Vec<&syn::Field> = match &input.data {
+        syn::Data::Struct(syn::DataStruct { fields, .. }) => fields.iter().collect(),
+        syn::Data::Enum(syn::DataEnum { variants, .. }) => variants.iter().flat_map(|variant| &variant.fields).collect(),
+        syn::Data::Union(_) => Vec::new(),
+    };
+    let mut errors = Vec::new();
+    check_attr_names(&input.attrs, STRUCT_META_NAMES, &mut errors);
+    // The values of attributes with an unknown name would be reported twice.
+    if errors.is_empty() {
+        errors.extend(check_struct_attr_values(input).into_iter().filter_map(Result::err));
+    }
+    for field in fields.iter() {
+        let name_errors = errors.len();
+        check_attr_names(&field.attrs, FIELD_META_NAMES, &mut errors);
+        if errors.len() == name_errors {
+            errors.extend(check_field_attr_values(field).into_iter().filter_map(Result::err));
+        }
+    }
+    check_method_names(input, &mut errors);
+    match errors.into_iter().reduce(|mut combined, err| {
+        combined.combine(err);
+        combined
+    }) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Check that the methods generated for the fields take neither the name of
+/// another method of the builder nor the name of each other.
+/// struct Command { merge: bool } => setter `merge` conflicts with the `merge` method of the builder
+fn check_method_names(input: &DeriveInput, errors: &mut Vec<syn::Error>) {
+    let cli = matches!(parse_cli(input), Ok(true));
+    let mut reserved = vec!["build", "missing_fields"];
+    if !matches!(parse_struct_attr_flag(input, "builder", "typestate"), Ok(true)) {
+        reserved.push("merge");
+    }
+    if let Ok(Some(_)) = parse_from_env(input) {
+        reserved.extend(["from_env", "from_map"]);
+    }
+    if cli {
+        reserved.extend(["parse_args", "help"]);
+    }
+    // Each variant of an enum has its own builder.
+    let builders: Vec<&syn::Fields> = match &input.data {
+        syn::Data::Struct(syn::DataStruct { fields, .. }) => vec![fields],
+        syn::Data::Enum(syn::DataEnum { variants, .. }) => variants.iter().map(|variant| &variant.fields).collect(),
+        syn::Data::Union(_) => Vec::new(),
+    };
+    for fields in builders {
+        let mut seen = Vec::new();
+        for (i, field) in fields.iter().enumerate().filter(|(_, field)| !is_skipped(field)) {
+            let mut field = field.clone();
+            if let Ok(Some(name)) = parse_field_name(&field) {
+                field.ident = Some(name);
+            } else if field.ident.is_none() {
+                field.ident = Some(Ident::new(&format!("_{}", i), field.span()));
+            }
+            if cli && matches!(cli_flag(&field), Ok(flag) if flag == "--help") {
+                errors.push(Error::new_spanned(&field, "`cli` flag `--help` is reserved for the help"));
+                continue;
+            }
+            for name in field_method_names(input, &field) {
+                if reserved.contains(&name.as_str()) {
+                    errors.push(Error::new_spanned(&field, format!("setter `{0}` conflicts with the `{0}` method of the builder", name)));
+                } else if seen.contains(&name) {
+                    errors.push(Error::new_spanned(&field, format!("setter `{}` is generated more than once", name)));
+                } else {
+                    seen.push(name);
+                }
+            }
+        }
+    }
+}
+
+/// Names of the methods generated for a field, as generate_setters and
+/// generate_introspection_methods name them.
+/// #[builder(each = "arg")] args: Vec<String> => [arg, args, extend_args, clear_args, is_args_set]
+fn field_method_names(input: &DeriveInput, field: &syn::Field) -> Vec<String> {
+    let ident = field.ident.as_ref().unwrap();
+    let setter = ident.unraw().to_string();
+    let name = |prefix: &str, suffix: &str| method_ident(prefix, ident, suffix).to_string();
+    let mut names = Vec::new();
+    if let Ok(Some(each)) = parse_field_attr_val(field, "builder", "each") {
+        if each != setter {
+            names.push(setter);
+        }
+        names.extend([each, name("extend_", ""), name("clear_", "")]);
+    } else {
+        names.push(setter);
+        if !is_sub_builder(field) && parse_generic_type(field, "Option").is_some() {
+            if matches!(is_setter_strip_option(input, field), Ok(true)) {
+                names.push(name("maybe_", ""));
+            }
+            names.push(name("unset_", ""));
+        }
+        if let Ok(Some(syn::Meta::Path(_))) = parse_field_attr(field, "builder", "try_setter") {
+            names.push(name("try_", ""));
+        }
+    }
+    names.push(name("is_", "_set"));
+    names
+}
+
+/// Check the names in the builder attributes, and in the lists nested in them.
+/// #[builder(eachh = "arg")] => unknown `builder` attribute `eachh`, did you mean `each`? ...
+fn check_attr_names(attrs: &[syn::Attribute], known: &[&str], errors: &mut Vec<syn::Error>) {
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("builder")) {
+        match attr.parse_meta() {
+            Ok(syn::Meta::List(syn::MetaList { nested, .. })) => check_nested_names("builder", &nested, known, errors),
+            Ok(meta) => errors.push(syn::Error::new_spanned(meta, "expected `builder(...)`")),
+            Err(err) => errors.push(err),
+        }
+    }
+}
+
+fn check_nested_names(
+    attr_name: &str,
+    nested: &syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+    known: &[&str],
+    errors: &mut Vec<syn::Error>,
+) {
+    let names: Vec<String> = known.iter().map(|name| format!("`{}`", name)).collect();
+    for nest in nested.iter() {
+        let meta = match nest {
+            syn::NestedMeta::Meta(meta) => meta,
+            syn::NestedMeta::Lit(lit) => {
+                errors.push(syn::Error::new_spanned(lit, format!(
+                    "expected `{}` attribute, one of {}", attr_name, names.join(", "),
+                )));
+                continue;
+            },
+        };
+        let name = path_to_string(meta.path());
+        if !known.contains(&name.as_str()) {
+            let suggestion = match suggest_name(&name, known) {
+                Some(similar) => format!(", did you mean `{}`?", similar),
+                None => ",".to_owned(),
+            };
+            errors.push(syn::Error::new_spanned(meta.path(), format!(
+                "unknown `{}` attribute `{}`{} expected one of {}", attr_name, name, suggestion, names.join(", "),
+            )));
+            continue;
+        }
+        let nested_known = match name.as_str() {
+            "setter" => SETTER_META_NAMES,
+            "build_fn" => BUILD_FN_META_NAMES,
+            "from_env" => FROM_ENV_META_NAMES,
+            "no_std" => NO_STD_META_NAMES,
+            _ => continue,
+        };
+        if let syn::Meta::List(syn::MetaList { nested, .. }) = meta {
+            check_nested_names(&name, nested, nested_known, errors);
+        }
+    }
+}
+
+/// Find the known name closest to a mistyped one.
+/// eachh => Some(each)
+fn suggest_name<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    known.iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, candidate)| *distance <= 2 && *distance < candidate.len())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two names.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if a_char == *b_char {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Check the values of the builder attributes on the struct.
+fn check_struct_attr_values(input: &DeriveInput) -> Vec<syn::Result<()>> {
+    let setter = parse_struct_attr(input, "builder", "setter");
+    vec![
+        parse_struct_attr_flag(input, "builder", "typestate").map(drop),
+        parse_no_std(input).map(drop),
+        parse_struct_attr_flag(input, "builder", "const").map(drop),
+        parse_struct_pattern(input).map(drop),
+        parse_build_fn_validate(input).map(drop),
+        parse_from_env(input).map(drop),
+        parse_cli(input).map(drop),
+        parse_builder_name(input).map(drop),
+        parse_builder_vis(input).map(drop),
+        parse_builder_derives(input).map(drop),
+        setter.clone().and_then(parse_setter_into).map(drop),
+        setter.clone().and_then(|meta| parse_setter_vis_meta(meta.as_ref())).map(drop),
+        setter.and_then(|meta| parse_setter_strip_option(meta.as_ref())).map(drop),
+    ]
+}
+
+/// Check the values of the builder attributes on a field.
+fn check_field_attr_values(field: &syn::Field) -> Vec<syn::Result<()>> {
+    let setter = parse_field_attr(field, "builder", "setter");
+    let mut results = vec![
+        parse_field_attr_val(field, "builder", "each").map(drop),
+        parse_field_default(field, &quote!(std)).map(drop),
+        parse_field_name(field).map(drop),
+        parse_merge_append(field).map(drop),
+        setter.clone().and_then(parse_setter_into).map(drop),
+        setter.clone().and_then(|meta| parse_setter_vis_meta(meta.as_ref())).map(drop),
+        setter.and_then(|meta| parse_setter_strip_option(meta.as_ref())).map(drop),
+    ];
+    for flag in ["sub_builder", "skip", "try_setter"] {
+        results.push(match parse_field_attr(field, "builder", flag) {
+            Ok(Some(syn::Meta::Path(_))) | Ok(None) => Ok(()),
+            Ok(Some(meta)) => Err(syn::Error::new_spanned(meta, format!("expected `builder({})`", flag))),
+            Err(err) => Err(err),
+        });
+    }
+    results
+}
+
 /// Parse the attributes on the field.
+/// #[builder(each = "arg")] => Some("arg")
 fn parse_field_attr_val(
     field: &syn::Field,
     attr_name: &str,
     meta_name: &str,
 ) -> syn::Result<Option<String>> {
-    for attr in field.attrs.iter() {
-        let syn::Attribute {
-            path,
-            ..
-        } = attr;
-        if let Some(syn::PathSegment { ident, ..}) = path.segments.last() {
-            // #[builder(each = "arg")]
-            if ident.to_string() == attr_name {
-                let meta_list = attr.parse_meta();
-                if let Ok(syn::Meta::List(syn::MetaList {
-                    nested, ..
-                })) = &meta_list {
-                    for nest in nested.iter() {
-                        match nest {
-                            syn::NestedMeta::Meta(
-                                syn::Meta::NameValue(name_value)) => {
-                                    let key = name_value.path.segments.last().unwrap().ident.to_string();
-                                    // each = "arg"
-                                    if key == meta_name {
-                                        if let syn::Lit::Str(lit) = &name_value.lit {
-                                            return Ok(Some(lit.value()));
-                                        }
-                                    }
-                            },
-                            _ => {}
-                        }
-                    }
+    match parse_field_attr(field, attr_name, meta_name)? {
+        Some(syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit), ..
+        })) => Ok(Some(lit.value())),
+        Some(meta) => Err(syn::Error::new_spanned(
+            meta,
+            format!("expected `{}({} = \"...\")`", attr_name, meta_name),
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Parse the meta of the attributes on the field.
+/// #[builder(each = "arg")] => Some(each = "arg")
+/// #[builder(default)]      => Some(default)
+fn parse_field_attr(
+    field: &syn::Field,
+    attr_name: &str,
+    meta_name: &str,
+) -> syn::Result<Option<syn::Meta>> {
+    parse_attr(&field.attrs, attr_name, meta_name, FIELD_META_NAMES, || {
+        "expected `builder(each = \"...\")`".to_owned()
+    })
+}
+
+/// Parse the meta of the attributes on the struct.
+/// #[builder(typestate)]       => Some(typestate)
+/// #[builder(setter(into))]    => Some(setter(into))
+fn parse_struct_attr(
+    input: &DeriveInput,
+    attr_name: &str,
+    meta_name: &str,
+) -> syn::Result<Option<syn::Meta>> {
+    parse_attr(&input.attrs, attr_name, meta_name, STRUCT_META_NAMES, || {
+        let names: Vec<String> = STRUCT_META_NAMES.iter().map(|name| format!("`{}`", name)).collect();
+        format!("expected `{}(...)` with one of {}", attr_name, names.join(", "))
+    })
+}
+
+/// Find meta_name in the attributes named attr_name. Any name not in known
+/// is reported with the message given by expected.
+fn parse_attr(
+    attrs: &[syn::Attribute],
+    attr_name: &str,
+    meta_name: &str,
+    known: &[&str],
+    expected: impl Fn() -> String,
+) -> syn::Result<Option<syn::Meta>> {
+    for attr in attrs.iter() {
+        if !attr.path.is_ident(attr_name) {
+            continue;
+        }
+        let meta_list = attr.parse_meta()?;
+        let nested = match &meta_list {
+            syn::Meta::List(syn::MetaList { nested, .. }) => nested,
+            _ => return Err(syn::Error::new_spanned(meta_list, expected())),
+        };
+        if let Some(found) = parse_nested_attr(&meta_list, nested, meta_name, known, &expected)? {
+            return Ok(Some(found));
+        }
+    }
+    Ok(None)
+}
+
+/// Parse the meta nested in a meta list.
+/// setter(into) => Some(into)
+fn parse_nested_meta(meta: &syn::Meta, meta_name: &str, known: &[&str]) -> syn::Result<Option<syn::Meta>> {
+    let names: Vec<String> = known.iter().map(|name| format!("`{}`", name)).collect();
+    let expected = || format!("expected `{}(...)` with one of {}", path_to_string(meta.path()), names.join(", "));
+    match meta {
+        syn::Meta::List(syn::MetaList { nested, .. }) => parse_nested_attr(meta, nested, meta_name, known, expected),
+        _ => Err(syn::Error::new_spanned(meta, expected())),
+    }
+}
+
+fn parse_nested_attr(
+    meta_list: &syn::Meta,
+    nested: &syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+    meta_name: &str,
+    known: &[&str],
+    expected: impl Fn() -> String,
+) -> syn::Result<Option<syn::Meta>> {
+    let mut found = None;
+    for nest in nested.iter() {
+        match nest {
+            syn::NestedMeta::Meta(meta) if known.iter().any(|name| meta.path().is_ident(name)) => {
+                if meta.path().is_ident(meta_name) {
+                    found = Some(meta.clone());
                 }
-                return Err(syn::Error::new_spanned(meta_list.unwrap(), "expected `builder(each = \"...\")`"))
-            }
+            },
+            _ => return Err(syn::Error::new_spanned(meta_list, expected())),
+        }
+    }
+    Ok(found)
+}
+
+fn path_to_string(path: &syn::Path) -> String {
+    path.segments.iter().map(|segment| segment.ident.to_string()).collect::<Vec<_>>().join("::")
+}
+
+/// Parse the name of the builder field, its setter and its error messages.
+/// #[builder(name = "x")] => Some(x)
+fn parse_field_name(field: &syn::Field) -> syn::Result<Option<Ident>> {
+    match parse_field_attr(field, "builder", "name")? {
+        Some(syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit), ..
+        })) => Ok(Some(lit.parse()?)),
+        Some(meta) => Err(syn::Error::new_spanned(meta, "expected `builder(name = \"...\")`")),
+        None => Ok(None),
+    }
+}
+
+/// Parse the default value of the field.
+/// #[builder(default)]         => Some(Default::default())
+/// #[builder(default = "...")] => Some(...)
+fn parse_field_default(field: &syn::Field, core: &proc_macro2::TokenStream) -> syn::Result<Option<proc_macro2::TokenStream>> {
+    match parse_field_attr(field, "builder", "default")? {
+        Some(syn::Meta::Path(_)) => Ok(Some(quote!(#core::default::Default::default()))),
+        Some(syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit), ..
+        })) => {
+            let expr: syn::Expr = lit.parse()?;
+            Ok(Some(quote!(#expr)))
+        },
+        Some(meta) => Err(syn::Error::new_spanned(meta, "expected `builder(default)` or `builder(default = \"...\")`")),
+        None => Ok(None),
+    }
+}
+
+/// A field is required unless it is an Option, has a default value, has
+/// the each attribute or has a sub builder, which reports its own fields.
+fn is_required_field(field: &syn::Field) -> bool {
+    if let Some(_ty) = parse_generic_type(field, "Option") {
+        return false;
+    }
+    if is_sub_builder(field) {
+        return false;
+    }
+    if let Ok(Some(_)) = parse_field_attr(field, "builder", "default") {
+        return false;
+    }
+    !matches!(parse_field_attr_val(field, "builder", "each"), Ok(Some(_)))
+}
+
+/// Parse the merge policy of an each field, whether merged collections are
+/// appended rather than replaced.
+/// #[builder(each = "arg", merge = "append")] => true
+fn parse_merge_append(field: &syn::Field) -> syn::Result<bool> {
+    let meta = match parse_field_attr(field, "builder", "merge")? {
+        Some(meta) => meta,
+        None => return Ok(false),
+    };
+    if parse_field_attr_val(field, "builder", "each")?.is_none() {
+        return Err(syn::Error::new_spanned(meta, "`merge` is only supported on `each` fields"));
+    }
+    match &meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit), ..
+        }) if lit.value() == "append" => Ok(true),
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit), ..
+        }) if lit.value() == "replace" => Ok(false),
+        _ => Err(syn::Error::new_spanned(meta, "expected `merge = \"append\"` or `\"replace\"`")),
+    }
+}
+
+/// Whether the field has the skip attribute.
+/// #[builder(skip)]
+fn is_skipped(field: &syn::Field) -> bool {
+    matches!(parse_field_attr(field, "builder", "skip"), Ok(Some(syn::Meta::Path(_))))
+}
+
+/// Whether the field has the sub_builder attribute.
+/// #[builder(sub_builder)]
+fn is_sub_builder(field: &syn::Field) -> bool {
+    matches!(parse_field_attr(field, "builder", "sub_builder"), Ok(Some(syn::Meta::Path(_))))
+}
+
+/// Parse the builder type of a field with the sub_builder attribute, which
+/// must keep the default name of its builder.
+/// Limits            => LimitsBuilder
+/// config::Limits<T> => config::LimitsBuilder<T>
+fn parse_sub_builder_type(field: &syn::Field) -> syn::Result<syn::Type> {
+    if let syn::Type::Path(syn::TypePath { qself: None, path }) = &field.ty {
+        let mut path = path.clone();
+        if let Some(segment) = path.segments.last_mut() {
+            segment.ident = Ident::new(&format!("{}Builder", segment.ident), segment.ident.span());
+            return Ok(syn::Type::Path(syn::TypePath { qself: None, path }));
+        }
+    }
+    Err(syn::Error::new_spanned(&field.ty, "`sub_builder` field type must be a struct deriving Builder"))
+}
+
+/// Local holding the built value of a field with a sub builder.
+/// limits => __limits
+fn sub_builder_local(field: &syn::Field) -> Ident {
+    let ident = field.ident.as_ref().unwrap();
+    Ident::new(&format!("__{}", ident.unraw()), ident.span())
+}
+
+/// Name of a field in the names made from it, the fields _0, _1, ... of tuple
+/// structs lose their underscore, raw identifiers lose their r#.
+/// current_dir => current_dir
+/// _0          => 0
+/// r#type      => type
+fn field_name(ident: &Ident) -> String {
+    let name = ident.unraw().to_string();
+    match name.strip_prefix('_') {
+        Some(index) if !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()) => index.to_owned(),
+        _ => name,
+    }
+}
+
+/// Name of a method made of the name of a field, which keeps the name snake
+/// case for the fields of tuple structs.
+/// ("is_", current_dir, "_set") => is_current_dir_set
+/// ("is_", _0, "_set")          => is_0_set
+/// ("is_", r#type, "_set")      => is_type_set
+fn method_ident(prefix: &str, ident: &Ident, suffix: &str) -> Ident {
+    Ident::new(&format!("{}{}{}", prefix, field_name(ident), suffix), ident.span())
+}
+
+/// Pointer to the builder taken by a const fn, which cannot drop it nor move
+/// its fields out one by one when they have destructors. The fields are read
+/// through __this instead.
+fn const_builder_ptr(core: &proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {
+        let __this = #core::mem::ManuallyDrop::new(self);
+        let __this = &__this as *const #core::mem::ManuallyDrop<Self> as *const Self;
+    }
+}
+
+/// Parse the type parameters of a typestate builder, one per required field.
+/// executable: String          => Some(__Executable)
+/// current_dir: Option<String> => None
+fn typestate_params(target: &Target) -> Vec<Option<Ident>> {
+    parse_fields(target, typestate_param)
+}
+
+/// Parse the type parameter of a required field.
+fn typestate_param(field: &syn::Field) -> Option<Ident> {
+    if !is_required_field(field) {
+        return None;
+    }
+    let ident = field.ident.as_ref().unwrap();
+    let camel: String = ident.unraw().to_string().split('_').map(|word| {
+        let mut chars = word.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    }).collect();
+    Some(Ident::new(&format!("__{}", camel), ident.span()))
+}
+
+/// Marker types of a typestate builder.
+/// CommandBuilder => (CommandBuilderSet, CommandBuilderUnset)
+fn typestate_marker_idents(builder_ident: &Ident) -> (Ident, Ident) {
+    (
+        Ident::new(&format!("{}Set", builder_ident), builder_ident.span()),
+        Ident::new(&format!("{}Unset", builder_ident), builder_ident.span()),
+    )
+}
+
+/// Append the type parameters of a typestate builder to the generics of the
+/// struct, each with the unset marker as default if given.
+/// <'a, B: Body> => <'a, B: Body, __Executable = CommandBuilderUnset>
+fn typestate_generics(generics: &syn::Generics, params: &[Ident], default: Option<&Ident>) -> syn::Generics {
+    let mut generics = generics.clone();
+    for param in params {
+        let mut type_param = syn::TypeParam::from(param.clone());
+        if let Some(default) = default {
+            type_param.eq_token = Some(Default::default());
+            type_param.default = Some(syn::parse_quote!(#default));
+        }
+        generics.params.push(syn::GenericParam::Type(type_param));
+    }
+    generics
+}
+
+/// Type of the builder with every field set.
+/// CommandBuilder<'a, B>
+/// CommandBuilder<'a, B, CommandBuilderSet, CommandBuilderSet> for typestate builders.
+fn set_builder_type(target: &Target, typestate: bool) -> proc_macro2::TokenStream {
+    let builder_ident = &target.builder_ident;
+    let generics = &target.input.generics;
+    if !typestate {
+        let (_, ty_generics, _) = generics.split_for_impl();
+        return quote!(#builder_ident #ty_generics);
+    }
+    let (set_ident, _) = typestate_marker_idents(builder_ident);
+    let args = generic_args(generics);
+    let set_markers = typestate_params(target).into_iter().flatten().map(|_| &set_ident);
+    quote!(#builder_ident<#(#args,)* #(#set_markers),*>)
+}
+
+/// Parse the arguments naming the generics of the struct.
+/// <'a, B: Body, const N: usize> => ['a, B, N]
+fn generic_args(generics: &syn::Generics) -> Vec<proc_macro2::TokenStream> {
+    generics.params.iter().map(|param| {
+        match param {
+            syn::GenericParam::Type(syn::TypeParam { ident, .. }) => quote!(#ident),
+            syn::GenericParam::Lifetime(syn::LifetimeDef { lifetime, .. }) => quote!(#lifetime),
+            syn::GenericParam::Const(syn::ConstParam { ident, .. }) => quote!(#ident),
+        }
+    }).collect()
+}
+
+/// Parse the flag attributes on the struct.
+/// #[builder(typestate)]
+fn parse_struct_attr_flag(
+    input: &DeriveInput,
+    attr_name: &str,
+    meta_name: &str,
+) -> syn::Result<bool> {
+    match parse_struct_attr(input, attr_name, meta_name)? {
+        Some(syn::Meta::Path(_)) => Ok(true),
+        Some(meta) => Err(syn::Error::new_spanned(meta, format!("expected `{}({})`", attr_name, meta_name))),
+        None => Ok(false),
+    }
+}
+
+/// Check that a const builder only uses what const fn can do: no trait
+/// methods, such as Into::into or Default::default, and no validation.
+fn check_const_builder(target: &Target) -> syn::Result<()> {
+    let input = target.input;
+    let meta = parse_struct_attr(input, "builder", "const")?;
+    if parse_struct_attr_flag(input, "builder", "typestate")? {
+        return Err(syn::Error::new_spanned(meta, "`const` is not supported by typestate builders"));
+    }
+    if parse_build_fn_validate(input)?.is_some() {
+        return Err(syn::Error::new_spanned(meta, "`const` is not supported with `build_fn(validate = \"...\")`"));
+    }
+    if parse_setter_into(parse_struct_attr(input, "builder", "setter")?)? {
+        return Err(syn::Error::new_spanned(meta, "`const` is not supported with `setter(into)`"));
+    }
+    for field in target.fields.iter() {
+        let unsupported = if parse_field_attr_val(field, "builder", "each")?.is_some() {
+            Some("`each`")
+        } else if is_sub_builder(field) {
+            Some("`sub_builder`")
+        } else if parse_setter_into(parse_field_attr(field, "builder", "setter")?)? {
+            Some("`setter(into)`")
+        } else if let Some(syn::Meta::Path(_)) = parse_field_attr(field, "builder", "default")? {
+            Some("`default` without a value")
+        } else if is_skipped(field) && parse_field_attr(field, "builder", "default")?.is_none() {
+            Some("`skip` without a default value")
+        } else {
+            None
+        };
+        if let Some(unsupported) = unsupported {
+            return Err(syn::Error::new_spanned(field, format!("{} is not supported by const builders", unsupported)));
+        }
+    }
+    Ok(())
+}
+
+/// How setters and build take the builder.
+#[derive(Clone, Copy, PartialEq)]
+enum Pattern {
+    /// Setters and build take the builder by value.
+    Owned,
+    /// Setters take &mut self and build takes the fields out of the builder.
+    Mutable,
+    /// Setters return a modified clone and build clones the fields.
+    Immutable,
+}
+
+/// Parse the pattern of the builder, mutable by default. Typestate and const
+/// builders always use the owned pattern.
+/// #[builder(pattern = "owned")] => Owned
+fn parse_struct_pattern(input: &DeriveInput) -> syn::Result<Pattern> {
+    let typestate = parse_struct_attr_flag(input, "builder", "typestate")?;
+    let constant = parse_struct_attr_flag(input, "builder", "const")?;
+    let meta = match parse_struct_attr(input, "builder", "pattern")? {
+        Some(meta) => meta,
+        None if typestate || constant => return Ok(Pattern::Owned),
+        None => return Ok(Pattern::Mutable),
+    };
+    let pattern = match &meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit), ..
+        }) => match lit.value().as_str() {
+            "owned" => Some(Pattern::Owned),
+            "mutable" => Some(Pattern::Mutable),
+            "immutable" => Some(Pattern::Immutable),
+            _ => None,
+        },
+        _ => None,
+    };
+    match pattern {
+        Some(Pattern::Owned) => Ok(Pattern::Owned),
+        Some(_) if typestate => Err(syn::Error::new_spanned(meta, "typestate builders always use `pattern = \"owned\"`")),
+        Some(_) if constant => Err(syn::Error::new_spanned(meta, "const builders always use `pattern = \"owned\"`")),
+        Some(pattern) => Ok(pattern),
+        None => Err(syn::Error::new_spanned(meta, "expected `pattern = \"owned\"`, `\"mutable\"` or `\"immutable\"`")),
+    }
+}
+
+/// Whether the setters of the field take impl Into<T>.
+/// #[builder(setter(into))] on the field or on the struct.
+fn is_setter_into(input: &DeriveInput, field: &syn::Field) -> syn::Result<bool> {
+    Ok(parse_setter_into(parse_field_attr(field, "builder", "setter")?)?
+        || parse_setter_into(parse_struct_attr(input, "builder", "setter")?)?)
+}
+
+/// Parse the into flag of the setter attribute.
+/// setter(into) => true
+fn parse_setter_into(meta: Option<syn::Meta>) -> syn::Result<bool> {
+    if let Some(meta) = meta {
+        match parse_nested_meta(&meta, "into", SETTER_META_NAMES)? {
+            Some(syn::Meta::Path(_)) => return Ok(true),
+            Some(meta) => return Err(syn::Error::new_spanned(meta, "expected `setter(into)`")),
+            None => {},
+        }
+    }
+    Ok(false)
+}
+
+/// Whether the setters of an Option field take the item of the Option, unless
+/// setter(strip_option = false) is given on the field or on the struct.
+fn is_setter_strip_option(input: &DeriveInput, field: &syn::Field) -> syn::Result<bool> {
+    if let Some(strip_option) = parse_setter_strip_option(parse_field_attr(field, "builder", "setter")?.as_ref())? {
+        return Ok(strip_option);
+    }
+    if let Some(strip_option) = parse_setter_strip_option(parse_struct_attr(input, "builder", "setter")?.as_ref())? {
+        return Ok(strip_option);
+    }
+    Ok(true)
+}
+
+/// Parse the strip_option flag of the setter attribute.
+/// setter(strip_option)         => Some(true)
+/// setter(strip_option = false) => Some(false)
+fn parse_setter_strip_option(meta: Option<&syn::Meta>) -> syn::Result<Option<bool>> {
+    if let Some(meta) = meta {
+        match parse_nested_meta(meta, "strip_option", SETTER_META_NAMES)? {
+            Some(syn::Meta::Path(_)) => return Ok(Some(true)),
+            Some(syn::Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Bool(lit), ..
+            })) => return Ok(Some(lit.value)),
+            Some(meta) => return Err(syn::Error::new_spanned(meta, "expected `setter(strip_option = false)`")),
+            None => {},
+        }
+    }
+    Ok(None)
+}
+
+/// Parse the visibility of the setters of the field, given on the field or on
+/// the struct, that of the field by default.
+/// #[builder(setter(vis = "pub(crate)"))] => pub(crate)
+fn parse_setter_vis(input: &DeriveInput, field: &syn::Field) -> syn::Result<syn::Visibility> {
+    if let Some(vis) = parse_setter_vis_meta(parse_field_attr(field, "builder", "setter")?.as_ref())? {
+        return Ok(vis);
+    }
+    if let Some(vis) = parse_setter_vis_meta(parse_struct_attr(input, "builder", "setter")?.as_ref())? {
+        return Ok(vis);
+    }
+    Ok(field.vis.clone())
+}
+
+/// Parse the vis of the setter attribute.
+/// setter(vis = "pub(crate)") => Some(pub(crate))
+fn parse_setter_vis_meta(meta: Option<&syn::Meta>) -> syn::Result<Option<syn::Visibility>> {
+    if let Some(meta) = meta {
+        match parse_nested_meta(meta, "vis", SETTER_META_NAMES)? {
+            Some(syn::Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Str(lit), ..
+            })) => return Ok(Some(lit.parse()?)),
+            Some(meta) => return Err(syn::Error::new_spanned(meta, "expected `setter(vis = \"...\")`")),
+            None => {},
+        }
+    }
+    Ok(None)
+}
+
+/// Parse the validation function of the build method.
+/// #[builder(build_fn(validate = "path::to::fn"))] => Some(path::to::fn)
+fn parse_build_fn_validate(input: &DeriveInput) -> syn::Result<Option<syn::Path>> {
+    if let Some(meta) = parse_struct_attr(input, "builder", "build_fn")? {
+        match parse_nested_meta(&meta, "validate", BUILD_FN_META_NAMES)? {
+            Some(syn::Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Str(lit), ..
+            })) => return Ok(Some(lit.parse()?)),
+            Some(meta) => return Err(syn::Error::new_spanned(meta, "expected `build_fn(validate = \"...\")`")),
+            None => {},
         }
     }
     Ok(None)
 }
 
-/// Custom error struct.
-fn error_struct() -> proc_macro2::TokenStream {
+/// Parse the name of the builder on the struct.
+/// #[builder(name = "CmdOpts")] => Some(CmdOpts)
+fn parse_builder_name(input: &DeriveInput) -> syn::Result<Option<Ident>> {
+    match parse_struct_attr(input, "builder", "name")? {
+        Some(syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit), ..
+        })) => Ok(Some(lit.parse()?)),
+        Some(meta) => Err(syn::Error::new_spanned(meta, "expected `builder(name = \"...\")`")),
+        None => Ok(None),
+    }
+}
+
+/// Parse the visibility of the builder, that of the struct by default.
+/// #[builder(vis = "pub(crate)")] => pub(crate)
+fn parse_builder_vis(input: &DeriveInput) -> syn::Result<syn::Visibility> {
+    match parse_struct_attr(input, "builder", "vis")? {
+        Some(syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit), ..
+        })) => lit.parse(),
+        Some(meta) => Err(syn::Error::new_spanned(meta, "expected `builder(vis = \"...\")`")),
+        None => Ok(input.vis.clone()),
+    }
+}
+
+/// Visibility of the methods returning the builder, pub unless the builder
+/// is given a visibility.
+fn builder_method_vis(target: &Target) -> proc_macro2::TokenStream {
+    match parse_struct_attr(target.input, "builder", "vis") {
+        Ok(Some(_)) => {
+            let vis = &target.vis;
+            quote!(#vis)
+        },
+        _ => quote!(pub),
+    }
+}
+
+/// Parse the traits derived for the builder.
+/// #[builder(derive(Debug, Clone))] => [Debug, Clone]
+fn parse_builder_derives(input: &DeriveInput) -> syn::Result<Vec<syn::Path>> {
+    let meta = match parse_struct_attr(input, "builder", "derive")? {
+        Some(meta) => meta,
+        None => return Ok(Vec::new()),
+    };
+    let expected = || syn::Error::new_spanned(&meta, "expected `builder(derive(Trait, ...))`");
+    match &meta {
+        syn::Meta::List(syn::MetaList { nested, .. }) => nested.iter().map(|nest| {
+            match nest {
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) => Ok(path.clone()),
+                _ => Err(expected()),
+            }
+        }).collect(),
+        _ => Err(expected()),
+    }
+}
+
+/// Options of the from_env attribute.
+struct FromEnv {
+    /// Prepended to the uppercase name of each field, empty by default.
+    prefix: String,
+    /// Separates the items of collections, "," by default.
+    separator: String,
+}
+
+/// Parse the from_env attribute on the struct. Typestate builders cannot be
+/// read from the environment, whose fields may be missing.
+/// #[builder(from_env(prefix = "APP_"))] => Some(FromEnv { prefix: "APP_", separator: "," })
+fn parse_from_env(input: &DeriveInput) -> syn::Result<Option<FromEnv>> {
+    let meta = match parse_struct_attr(input, "builder", "from_env")? {
+        Some(meta) => meta,
+        None => return Ok(None),
+    };
+    if parse_struct_attr_flag(input, "builder", "typestate")? {
+        return Err(syn::Error::new_spanned(meta, "`from_env` is not supported by typestate builders"));
+    }
+    // The environment and HashMap are only available with std.
+    if parse_no_std(input)?.is_some() {
+        return Err(syn::Error::new_spanned(meta, "`from_env` is not supported by no_std builders"));
+    }
+    if let syn::Meta::Path(_) = meta {
+        return Ok(Some(FromEnv { prefix: String::new(), separator: ",".to_owned() }));
+    }
+    let mut values = Vec::new();
+    for (name, default) in [("prefix", ""), ("separator", ",")] {
+        let value = match parse_nested_meta(&meta, name, FROM_ENV_META_NAMES)? {
+            Some(syn::Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Str(lit), ..
+            })) => lit.value(),
+            Some(meta) => return Err(syn::Error::new_spanned(meta, format!("expected `from_env({} = \"...\")`", name))),
+            None => default.to_owned(),
+        };
+        values.push(value);
+    }
+    let separator = values.pop().unwrap();
+    let prefix = values.pop().unwrap();
+    if separator.is_empty() {
+        return Err(syn::Error::new_spanned(meta, "`separator` must not be empty"));
+    }
+    Ok(Some(FromEnv { prefix, separator }))
+}
+
+/// Options of the no_std attribute.
+struct NoStd {
+    /// Whether the error enum implements core::error::Error, which needs Rust 1.81.
+    core_error: bool,
+}
+
+/// Parse the no_std attribute on the struct.
+/// #[builder(no_std)]             => Some(NoStd { core_error: false })
+/// #[builder(no_std(core_error))] => Some(NoStd { core_error: true })
+fn parse_no_std(input: &DeriveInput) -> syn::Result<Option<NoStd>> {
+    let meta = match parse_struct_attr(input, "builder", "no_std")? {
+        Some(meta) => meta,
+        None => return Ok(None),
+    };
+    if let syn::Meta::Path(_) = meta {
+        return Ok(Some(NoStd { core_error: false }));
+    }
+    let core_error = match parse_nested_meta(&meta, "core_error", NO_STD_META_NAMES)? {
+        Some(syn::Meta::Path(_)) => true,
+        Some(meta) => return Err(syn::Error::new_spanned(meta, "expected `no_std(core_error)`")),
+        None => false,
+    };
+    Ok(Some(NoStd { core_error }))
+}
+
+/// Parse the cli flag on the struct. Typestate builders cannot be parsed from
+/// arguments, which may miss fields, and parsing needs std.
+/// #[builder(cli)] => true
+fn parse_cli(input: &DeriveInput) -> syn::Result<bool> {
+    if !parse_struct_attr_flag(input, "builder", "cli")? {
+        return Ok(false);
+    }
+    let meta = parse_struct_attr(input, "builder", "cli")?;
+    if parse_struct_attr_flag(input, "builder", "typestate")? {
+        return Err(syn::Error::new_spanned(meta, "`cli` is not supported by typestate builders"));
+    }
+    if parse_no_std(input)?.is_some() {
+        return Err(syn::Error::new_spanned(meta, "`cli` is not supported by no_std builders"));
+    }
+    Ok(true)
+}
+
+/// Name of the error enum of a builder.
+/// CommandBuilder => CommandBuilderError
+fn error_ident(builder_ident: &Ident) -> Ident {
+    Ident::new(&format!("{}Error", builder_ident), builder_ident.span())
+}
+
+/// Custom error enum, one per builder, with only the variants the builder can
+/// return. ValidationError is returned by validations and by sub builders,
+/// InvalidValue by the methods of from_env and cli, and the other variants
+/// by the methods of cli. With no_std, the enum only implements
+/// core::error::Error with #[builder(no_std(core_error))], so that older
+/// compilers can build the others.
+fn error_enum(target: &Target) -> proc_macro2::TokenStream {
+    let vis = &target.vis;
+    let (core, alloc) = (&target.core, &target.alloc);
+    let error_ident = error_ident(&target.builder_ident);
+    let doc = doc_attr(format!("Error returned when a [`{}`] cannot be built.", target_name(target)));
+    let validation = matches!(parse_build_fn_validate(target.input), Ok(Some(_)))
+        || parse_fields(target, is_sub_builder).into_iter().any(|sub_builder| sub_builder);
+    let cli = matches!(parse_cli(target.input), Ok(true));
+    let parse = matches!(parse_from_env(target.input), Ok(Some(_))) || cli;
+    let error_impl_stream = match parse_no_std(target.input) {
+        Ok(Some(NoStd { core_error: false })) => quote!(),
+        _ => quote! {
+            impl #core::error::Error for #error_ident {
+            }
+        },
+    };
+    let mut variant_stream = Vec::new();
+    let mut display_stream = Vec::new();
+    if validation {
+        variant_stream.push(quote! {
+            /// Message of the failed validation.
+            ValidationError(#alloc::string::String),
+        });
+        display_stream.push(quote! {
+            #error_ident::ValidationError(message) => write!(f, "{}", message),
+        });
+    }
+    if parse {
+        variant_stream.push(quote! {
+            /// A value read by from_env, from_map or parse_args failed to parse.
+            InvalidValue {
+                /// Name of the field.
+                field: #alloc::string::String,
+                /// Value that failed to parse.
+                value: #alloc::string::String,
+                /// Message of the parse error.
+                message: #alloc::string::String,
+            },
+        });
+        display_stream.push(quote! {
+            #error_ident::InvalidValue { field, value, message } => {
+                write!(f, "invalid value `{}` for `{}`: {}", value, field, message)
+            },
+        });
+    }
+    if cli {
+        variant_stream.push(quote! {
+            /// An argument read by parse_args is not a flag of the builder.
+            UnknownArgument(#alloc::string::String),
+            /// A flag read by parse_args has no value.
+            MissingValue(#alloc::string::String),
+            /// Help requested by `--help` in parse_args, to be printed.
+            Help(#alloc::string::String),
+        });
+        display_stream.push(quote! {
+            #error_ident::UnknownArgument(arg) => write!(f, "unknown argument `{}`", arg),
+            #error_ident::MissingValue(flag) => write!(f, "missing value for `{}`", flag),
+            #error_ident::Help(help) => write!(f, "{}", help),
+        });
+    }
+    // A catch-all arm would be unreachable without other variants.
+    let other_fields_stream = if variant_stream.is_empty() {
+        quote!()
+    } else {
+        quote!(_ => &[],)
+    };
     quote! {
-        pub struct BuildError(String);
-        impl std::error::Error for BuildError {
+        #doc
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #vis enum #error_ident {
+            /// Paths of the required fields that were not set.
+            MissingFields(#alloc::vec::Vec<#alloc::string::String>),
+            #(#variant_stream)*
         }
 
-        impl std::fmt::Display for BuildError {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                write!(f, "build {} error", self.0)
+        impl #error_ident {
+            /// Paths of the fields that were not set, empty for other errors.
+            pub fn missing_fields(&self) -> &[#alloc::string::String] {
+                match self {
+                    #error_ident::MissingFields(fields) => fields,
+                    #other_fields_stream
+                }
             }
         }
 
-        impl std::fmt::Debug for BuildError {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                f.debug_tuple("BuildError").field(&self.0).finish()
+        #error_impl_stream
+
+        impl #core::fmt::Display for #error_ident {
+            fn fmt(&self, f: &mut #core::fmt::Formatter<'_>) -> #core::fmt::Result {
+                match self {
+                    #error_ident::MissingFields(fields) => {
+                        for (i, field) in fields.iter().enumerate() {
+                            if i > 0 {
+                                write!(f, ", ")?;
+                            }
+                            write!(f, "`{}`", field)?;
+                        }
+                        write!(f, " must be initialized")
+                    },
+                    #(#display_stream)*
+                }
             }
         }
     }
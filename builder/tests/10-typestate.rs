@@ -0,0 +1,32 @@
+// With #[builder(typestate)] the builder carries one type parameter per
+// required field, so `build` only exists once every required field has been
+// set and returns the struct directly instead of a Result.
+//
+//     pub struct CommandBuilder<__Executable = CommandBuilderUnset> { ... }
+//
+//     impl CommandBuilder<CommandBuilderSet> {
+//         pub fn build(self) -> Command { ... }
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .arg("build".to_owned())
+        .executable("cargo".to_owned())
+        .arg("--release".to_owned())
+        .build();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert!(command.current_dir.is_none());
+}
@@ -0,0 +1,17 @@
+// A typestate builder whose required fields are not all set has no `build`
+// method, so forgetting a field is a compile error rather than a runtime one.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let _command = Command::builder()
+        .current_dir("..".to_owned())
+        .build();
+}
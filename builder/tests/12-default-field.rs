@@ -0,0 +1,37 @@
+// Fields marked with #[builder(default)] or #[builder(default = "...")] are no
+// longer required; `build` fills them in with Default::default() or the given
+// expression when they were never set.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(default)]
+    args: Vec<String>,
+    #[builder(default = "String::from(\"..\")")]
+    current_dir: String,
+    #[builder(default = "Some(8)")]
+    jobs: Option<u32>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+
+    assert!(command.args.is_empty());
+    assert_eq!(command.current_dir, "..");
+    assert_eq!(command.jobs, Some(8));
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir("/tmp".to_owned())
+        .jobs(1)
+        .build()
+        .unwrap();
+
+    assert_eq!(command.current_dir, "/tmp");
+    assert_eq!(command.jobs, Some(1));
+}
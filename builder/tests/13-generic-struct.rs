@@ -0,0 +1,64 @@
+// The builder of a generic struct carries the struct's lifetimes, type
+// parameters, const generics and where clauses.
+//
+//     pub struct RequestBuilder<'a, B: Body, const N: usize> where B: Clone {
+//         ...
+//     }
+
+use derive_builder::Builder;
+
+pub trait Body {
+    fn len(&self) -> usize;
+}
+
+impl Body for Vec<u8> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+}
+
+#[derive(Builder)]
+pub struct Request<'a, B: Body, const N: usize>
+where
+    B: Clone,
+{
+    path: &'a str,
+    body: B,
+    headers: [&'a str; N],
+    #[builder(each = "query")]
+    queries: Vec<(&'a str, &'a str)>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Response<'a, B> {
+    status: u16,
+    body: B,
+    reason: Option<&'a str>,
+}
+
+fn main() {
+    let path = String::from("/index.html");
+    let request = Request::builder()
+        .path(&path)
+        .body(vec![1u8, 2, 3])
+        .headers(["accept", "host"])
+        .query(("page", "1"))
+        .build()
+        .unwrap();
+
+    assert_eq!(request.path, "/index.html");
+    assert_eq!(request.body.len(), 3);
+    assert_eq!(request.headers.len(), 2);
+    assert_eq!(request.queries, vec![("page", "1")]);
+
+    let response = Response::builder()
+        .reason("OK")
+        .body("hello")
+        .status(200)
+        .build();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, "hello");
+    assert_eq!(response.reason, Some("OK"));
+}
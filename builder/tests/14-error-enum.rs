@@ -0,0 +1,34 @@
+// Every builder gets its own error enum, so several builders can live in one
+// module and callers can match on the reason `build` failed. It only has the
+// variants its builder can return, so matches need no catch-all arm.
+//
+//     pub enum CommandBuilderError {
+//         MissingFields(Vec<String>),
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+pub struct Limits {
+    max_mem: u64,
+}
+
+fn main() {
+    let err = Command::builder().build().err().unwrap();
+    assert_eq!(err, CommandBuilderError::MissingFields(vec!["executable".to_owned()]));
+    assert_eq!(err.to_string(), "`executable` must be initialized");
+
+    let err: Box<dyn std::error::Error> = Box::new(err);
+    let _ = err;
+
+    match Limits::builder().build() {
+        Err(LimitsBuilderError::MissingFields(fields)) => assert_eq!(fields, ["max_mem"]),
+        Ok(_) => unreachable!(),
+    }
+}
@@ -0,0 +1,24 @@
+// `build` reports every required field that was never set at once, in the
+// order the fields are declared, instead of stopping at the first one.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+    current_dir: String,
+    #[builder(default)]
+    jobs: u32,
+}
+
+fn main() {
+    let err = Command::builder()
+        .args(vec![])
+        .build()
+        .err()
+        .unwrap();
+
+    assert_eq!(err, CommandBuilderError::MissingFields(vec!["executable".to_owned(), "current_dir".to_owned()]));
+    assert_eq!(err.to_string(), "`executable`, `current_dir` must be initialized");
+}
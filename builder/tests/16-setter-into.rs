@@ -0,0 +1,58 @@
+// With #[builder(setter(into))] a setter accepts anything that converts into
+// the field type, including the one-at-a-time setters of `each` fields. The
+// attribute may be put on a single field or on the struct for every field.
+//
+//     fn executable(&mut self, executable: impl Into<String>) -> &mut Self {
+//         self.executable = Some(executable.into());
+//         self
+//     }
+
+use derive_builder::Builder;
+use std::path::PathBuf;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(setter(into))]
+    executable: String,
+    #[builder(each = "arg", setter(into))]
+    args: Vec<String>,
+    current_dir: Option<PathBuf>,
+}
+
+#[derive(Builder)]
+#[builder(setter(into))]
+pub struct Limits {
+    max_mem: u64,
+    name: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(typestate, setter(into))]
+pub struct Job {
+    name: String,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo")
+        .arg("build")
+        .arg(String::from("--release"))
+        .current_dir(PathBuf::from(".."))
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+
+    let limits = Limits::builder()
+        .max_mem(1024u32)
+        .name("small")
+        .build()
+        .unwrap();
+
+    assert_eq!(limits.max_mem, 1024);
+    assert_eq!(limits.name.as_deref(), Some("small"));
+
+    let job = Job::builder().name("test").build();
+    assert_eq!(job.name, "test");
+}
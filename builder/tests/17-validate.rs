@@ -0,0 +1,71 @@
+// #[builder(build_fn(validate = "..."))] names a function that checks the
+// builder once every required field is set and before the struct is
+// constructed. Its error is returned from `build` as a ValidationError.
+//
+//     fn validate(builder: &CommandBuilder) -> Result<(), String>;
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(build_fn(validate = "validate_command"))]
+pub struct Command {
+    executable: String,
+    port: Option<u32>,
+}
+
+fn validate_command(builder: &CommandBuilder) -> Result<(), String> {
+    if builder.executable.as_deref() == Some("") {
+        return Err("executable must not be empty".to_owned());
+    }
+    match builder.port {
+        Some(port) if !(1..=65535).contains(&port) => Err(format!("port {} is out of range", port)),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Builder)]
+#[builder(typestate, build_fn(validate = "checks::validate_job"))]
+pub struct Job {
+    name: String,
+}
+
+mod checks {
+    use super::{JobBuilder, JobBuilderSet};
+
+    pub fn validate_job(builder: &JobBuilder<JobBuilderSet>) -> Result<(), &'static str> {
+        match builder.name.as_deref() {
+            Some("") => Err("name must not be empty"),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .port(8080)
+        .build()
+        .unwrap();
+    assert_eq!(command.port, Some(8080));
+
+    let err = Command::builder()
+        .executable("cargo".to_owned())
+        .port(70000)
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err, CommandBuilderError::ValidationError("port 70000 is out of range".to_owned()));
+
+    let err = Command::builder()
+        .executable(String::new())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), "executable must not be empty");
+
+    let job = Job::builder().name("test".to_owned()).build().unwrap();
+    assert_eq!(job.name, "test");
+
+    let err = Job::builder().name(String::new()).build().err().unwrap();
+    assert_eq!(err, JobBuilderError::ValidationError("name must not be empty".to_owned()));
+}
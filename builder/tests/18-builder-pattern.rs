@@ -0,0 +1,49 @@
+// #[builder(pattern = "...")] selects how setters and `build` take the
+// builder. The default is "mutable". With "owned" setters consume the builder
+// and return it, and `build` consumes it too. With "immutable" setters return
+// a modified clone, so one builder can be used as a template for many values.
+//
+//     fn executable(mut self, executable: String) -> Self   // owned
+//     fn executable(&self, executable: String) -> Self      // immutable
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable")]
+pub struct Request {
+    host: String,
+    path: String,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "mutable")]
+pub struct Limits {
+    max_mem: u64,
+}
+
+fn main() {
+    let builder = Command::builder().executable("cargo".to_owned());
+    let command = builder.arg("build".to_owned()).build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+
+    let template = Request::builder().host("example.com".to_owned());
+    let index = template.path("/".to_owned()).build().unwrap();
+    let about = template.path("/about".to_owned()).build().unwrap();
+    assert_eq!(index.host, "example.com");
+    assert_eq!(index.path, "/");
+    assert_eq!(about.host, "example.com");
+    assert_eq!(about.path, "/about");
+    assert!(template.build().is_err());
+
+    let limits = Limits::builder().max_mem(1).build().unwrap();
+    assert_eq!(limits.max_mem, 1);
+}
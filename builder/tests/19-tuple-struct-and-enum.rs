@@ -0,0 +1,60 @@
+// Tuple structs get positional setters _0, _1, ... unless a field is named
+// with #[builder(name = "...")]. Enums get one builder per variant with
+// fields, created by a snake_case method named after the variant, in which a
+// run of capitals forms one word.
+//
+//     impl Shape {
+//         pub fn circle_builder() -> ShapeCircleBuilder { ... }
+//         pub fn rect_builder() -> ShapeRectBuilder { ... }
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Point(pub f64, pub f64);
+
+#[derive(Builder)]
+pub struct Span(#[builder(name = "start")] pub usize, #[builder(name = "end")] pub usize);
+
+#[derive(Builder, Debug, PartialEq)]
+pub enum Shape {
+    Circle {
+        radius: f64,
+        label: Option<String>,
+    },
+    Rect(#[builder(name = "width")] f64, #[builder(name = "height")] f64),
+    Empty,
+}
+
+#[derive(Builder)]
+pub enum Message {
+    HTTPRequest { url: String },
+    Http2Ping { payload: u64 },
+}
+
+fn main() {
+    let point = Point::builder()._0(1.0)._1(2.0).build().unwrap();
+    assert_eq!((point.0, point.1), (1.0, 2.0));
+
+    let err = Point::builder()._1(2.0).build().err().unwrap();
+    assert_eq!(err, PointBuilderError::MissingFields(vec!["_0".to_owned()]));
+
+    let request = Message::http_request_builder().url("/".to_owned()).build().unwrap();
+    assert!(matches!(request, Message::HTTPRequest { url } if url == "/"));
+    let ping = Message::http2_ping_builder().payload(7).build().unwrap();
+    assert!(matches!(ping, Message::Http2Ping { payload: 7 }));
+
+    let span = Span::builder().start(3).end(5).build().unwrap();
+    assert_eq!((span.0, span.1), (3, 5));
+
+    let circle = Shape::circle_builder().radius(1.5).build().unwrap();
+    assert_eq!(circle, Shape::Circle { radius: 1.5, label: None });
+
+    let rect = Shape::rect_builder().width(2.0).height(3.0).build().unwrap();
+    assert_eq!(rect, Shape::Rect(2.0, 3.0));
+
+    let err = Shape::rect_builder().width(2.0).build().err().unwrap();
+    assert_eq!(err, ShapeRectBuilderError::MissingFields(vec!["height".to_owned()]));
+
+    assert_ne!(Shape::Empty, circle);
+}
@@ -0,0 +1,82 @@
+// #[builder(each = "...")] works with any collection that implements Default
+// and Extend, not only Vec. Items have the type the collection iterates over,
+// so newtypes and collections with more type arguments work too. Maps, whose
+// type name ends with Map, get a setter taking the key and the value.
+//
+//     fn env(&mut self, key: String, value: String) -> &mut Self
+
+use derive_builder::Builder;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque};
+
+#[derive(Default)]
+pub struct Args(Vec<String>);
+
+impl Extend<String> for Args {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for Args {
+    type Item = String;
+    type IntoIter = std::vec::IntoIter<String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[derive(Builder)]
+pub struct Job {
+    #[builder(each = "arg")]
+    args: Args,
+    #[builder(each = "tag", setter(into))]
+    tags: HashSet<String, RandomState>,
+}
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(each = "arg")]
+    args: VecDeque<String>,
+    #[builder(each = "env")]
+    env: BTreeMap<String, String>,
+    #[builder(each = "header", setter(into))]
+    headers: HashMap<String, String>,
+    #[builder(each = "feature")]
+    features: HashSet<String>,
+    #[builder(each = "target")]
+    targets: BTreeSet<u32>,
+    #[builder(each = "step")]
+    steps: LinkedList<u8>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .arg("build".to_owned())
+        .arg("--release".to_owned())
+        .env("RUST_LOG".to_owned(), "debug".to_owned())
+        .env("CARGO_HOME".to_owned(), "/cargo".to_owned())
+        .header("accept", "*/*")
+        .feature("std".to_owned())
+        .feature("std".to_owned())
+        .target(2)
+        .target(1)
+        .step(7)
+        .build()
+        .unwrap();
+
+    assert_eq!(command.args, ["build", "--release"]);
+    assert_eq!(command.env.keys().collect::<Vec<_>>(), ["CARGO_HOME", "RUST_LOG"]);
+    assert_eq!(command.headers["accept"], "*/*");
+    assert_eq!(command.features.len(), 1);
+    assert_eq!(command.targets.iter().collect::<Vec<_>>(), [&1, &2]);
+    assert_eq!(command.steps.front(), Some(&7));
+
+    let command = Command::builder().build().unwrap();
+    assert!(command.env.is_empty());
+
+    let job = Job::builder().arg("a".to_owned()).arg("b".to_owned()).tag("x").build().unwrap();
+    assert_eq!(job.args.0, ["a", "b"]);
+    assert!(job.tags.contains("x"));
+}
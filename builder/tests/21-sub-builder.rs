@@ -0,0 +1,142 @@
+// A field with #[builder(sub_builder)] holds the builder of its own type,
+// which also derives Builder. The setter returns that builder so deep values
+// can be filled in place, and the outer `build` builds it, reporting missing
+// fields with the path of the field. A failed build keeps the fields of the
+// sub builders so the builder can be fixed and built again. Sub builders may
+// use any pattern.
+//
+//     fn limits(&mut self) -> &mut LimitsBuilder
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Limits {
+    max_mem: u64,
+    #[builder(default = "4")]
+    max_jobs: u32,
+}
+
+#[derive(Builder)]
+#[builder(build_fn(validate = "validate_network"))]
+pub struct Network {
+    port: u32,
+}
+
+fn validate_network(builder: &NetworkBuilder) -> Result<(), String> {
+    match builder.port {
+        Some(0) => Err("port must not be 0".to_owned()),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(sub_builder)]
+    limits: Limits,
+    #[builder(sub_builder)]
+    network: Network,
+}
+
+#[derive(Builder)]
+#[builder(build_fn(validate = "validate_service"))]
+pub struct Service {
+    #[builder(default)]
+    name: String,
+    #[builder(sub_builder)]
+    limits: Limits,
+}
+
+fn validate_service(builder: &ServiceBuilder) -> Result<(), String> {
+    match &builder.name {
+        Some(name) if !name.is_empty() => Ok(()),
+        _ => Err("name must not be empty".to_owned()),
+    }
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(pattern = "owned")]
+pub struct Quota {
+    max_files: u32,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(pattern = "immutable")]
+pub struct Timeout {
+    secs: u64,
+}
+
+#[derive(Builder)]
+pub struct Task {
+    name: String,
+    #[builder(sub_builder)]
+    quota: Quota,
+    #[builder(sub_builder)]
+    timeout: Timeout,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Job {
+    #[builder(sub_builder)]
+    limits: Limits,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    builder.executable("cargo".to_owned());
+    builder.limits().max_mem(1024);
+    builder.network().port(8080);
+    let command = builder.build().unwrap();
+    assert_eq!(command.limits, Limits { max_mem: 1024, max_jobs: 4 });
+    assert_eq!(command.network.port, 8080);
+
+    let err = Command::builder().build().err().unwrap();
+    assert_eq!(err.missing_fields(), ["executable", "limits.max_mem", "network.port"]);
+    assert_eq!(err.to_string(), "`executable`, `limits.max_mem`, `network.port` must be initialized");
+
+    let mut builder = Command::builder();
+    builder.executable("cargo".to_owned());
+    builder.limits().max_mem(1024);
+    builder.network().port(0);
+    let err = builder.build().err().unwrap();
+    assert_eq!(err, CommandBuilderError::ValidationError("network: port must not be 0".to_owned()));
+
+    // Missing fields and validation errors leave the sub builders as they were.
+    let mut builder = Command::builder();
+    builder.limits().max_mem(5);
+    builder.network().port(0);
+    let err = builder.build().err().unwrap();
+    assert_eq!(err.missing_fields(), ["executable"]);
+    builder.executable("cargo".to_owned());
+    let err = builder.build().err().unwrap();
+    assert_eq!(err, CommandBuilderError::ValidationError("network: port must not be 0".to_owned()));
+    assert!(!builder.limits().is_max_jobs_set());
+    builder.network().port(8080);
+    let command = builder.build().unwrap();
+    assert_eq!(command.limits, Limits { max_mem: 5, max_jobs: 4 });
+    assert_eq!(command.network.port, 8080);
+
+    let mut builder = Service::builder();
+    builder.limits().max_mem(5);
+    let err = builder.build().err().unwrap();
+    assert_eq!(err, ServiceBuilderError::ValidationError("name must not be empty".to_owned()));
+    builder.name("web".to_owned());
+    let service = builder.build().unwrap();
+    assert_eq!(service.limits, Limits { max_mem: 5, max_jobs: 4 });
+
+    let mut builder = Task::builder();
+    assert_eq!(builder.build().err().unwrap().missing_fields(), ["name", "quota.max_files", "timeout.secs"]);
+    *builder.quota() = Quota::builder().max_files(3);
+    *builder.timeout() = Timeout::builder().secs(5);
+    assert_eq!(builder.build().err().unwrap().missing_fields(), ["name"]);
+    builder.name("index".to_owned());
+    let task = builder.build().unwrap();
+    assert_eq!(task.quota, Quota { max_files: 3 });
+    assert_eq!(task.timeout, Timeout { secs: 5 });
+
+    let mut builder = Job::builder();
+    builder.limits().max_mem(1).max_jobs(2);
+    let job = builder.build().unwrap();
+    assert_eq!(job.limits, Limits { max_mem: 1, max_jobs: 2 });
+}
@@ -0,0 +1,62 @@
+// An existing value converts back into a builder with every field set, so a
+// copy of it can be tweaked and built again. `to_builder` is available when
+// the struct is Clone, `From` takes the value by move.
+//
+//     impl Command {
+//         pub fn to_builder(&self) -> CommandBuilder { ... }
+//     }
+//
+//     impl From<Command> for CommandBuilder { ... }
+
+use derive_builder::Builder;
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+pub struct Limits {
+    max_mem: u64,
+}
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(sub_builder)]
+    limits: Limits,
+}
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+#[builder(typestate)]
+pub struct Point(i32, i32);
+
+// Not Clone, so only the From conversion applies.
+#[derive(Builder, Debug, PartialEq)]
+pub struct Job {
+    name: String,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    builder.executable("cargo".to_owned()).arg("build".to_owned());
+    builder.limits().max_mem(1024);
+    let base = builder.build().unwrap();
+
+    let mut builder = base.to_builder();
+    builder.arg("--release".to_owned());
+    builder.limits().max_mem(2048);
+    let release = builder.build().unwrap();
+    assert_eq!(release.executable, "cargo");
+    assert_eq!(release.args, vec!["build", "--release"]);
+    assert_eq!(release.current_dir, None);
+    assert_eq!(release.limits, Limits { max_mem: 2048 });
+    assert_eq!(base.args, vec!["build"]);
+
+    let mut builder = CommandBuilder::from(base.clone());
+    assert_eq!(builder.build().unwrap(), base);
+
+    let point = Point::builder()._0(1)._1(2).build();
+    assert_eq!(point.to_builder()._1(3).build(), Point(1, 3));
+
+    let job = JobBuilder::from(Job { name: "test".to_owned() }).build().unwrap();
+    assert_eq!(job, Job { name: "test".to_owned() });
+}
@@ -0,0 +1,65 @@
+// With #[builder(from_env(prefix = "..."))] the builder can be populated from
+// environment variables, one per field named after the prefix and the field
+// in uppercase. Values are parsed with FromStr and the items of `each`
+// collections are split on the separator, "," unless given. from_map reads
+// the same variables from a map.
+//
+//     pub fn from_env() -> Result<CommandBuilder, CommandBuilderError>
+//     pub fn from_map(map: &HashMap<String, String>) -> Result<CommandBuilder, CommandBuilderError>
+
+use derive_builder::Builder;
+use std::collections::HashMap;
+
+#[derive(Builder, Debug)]
+#[builder(from_env(prefix = "APP_"))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(default = "1")]
+    jobs: u32,
+}
+
+#[derive(Builder, Debug)]
+#[builder(from_env(prefix = "SERVER_", separator = ":"))]
+pub struct Server {
+    #[builder(each = "port")]
+    ports: Vec<u16>,
+}
+
+fn main() {
+    let mut map = HashMap::new();
+    map.insert("APP_EXECUTABLE".to_owned(), "cargo".to_owned());
+    map.insert("APP_ARGS".to_owned(), "build,--release".to_owned());
+    map.insert("OTHER_JOBS".to_owned(), "x".to_owned());
+    let mut builder = CommandBuilder::from_map(&map).unwrap();
+    let command = builder.arg("--locked".to_owned()).build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release", "--locked"]);
+    assert_eq!(command.current_dir, None);
+    assert_eq!(command.jobs, 1);
+
+    map.insert("APP_JOBS".to_owned(), "many".to_owned());
+    let err = CommandBuilder::from_map(&map).err().unwrap();
+    assert_eq!(
+        err,
+        CommandBuilderError::InvalidValue {
+            field: "jobs".to_owned(),
+            value: "many".to_owned(),
+            message: "invalid digit found in string".to_owned(),
+        }
+    );
+    assert_eq!(err.to_string(), "invalid value `many` for `jobs`: invalid digit found in string");
+
+    let err = CommandBuilder::from_map(&HashMap::new()).unwrap().build().unwrap_err();
+    assert_eq!(err.missing_fields(), ["executable"]);
+
+    std::env::set_var("SERVER_PORTS", "80:443");
+    let server = ServerBuilder::from_env().unwrap().build().unwrap();
+    assert_eq!(server.ports, vec![80, 443]);
+
+    std::env::set_var("SERVER_PORTS", "80:http");
+    let err = ServerBuilder::from_env().err().unwrap();
+    assert_eq!(err.to_string(), "invalid value `http` for `ports`: invalid digit found in string");
+}
@@ -0,0 +1,57 @@
+// The builder can be given another name, another visibility and derived
+// traits with #[builder(name = "...", vis = "...", derive(...))]. Its error
+// enum is named after it.
+//
+//     #[derive(Debug, Clone, PartialEq)]
+//     pub(crate) struct CmdOpts { ... }
+//     pub(crate) enum CmdOptsError { ... }
+
+use derive_builder::Builder;
+
+// Written by hand, the generated builder must not collide with it.
+pub struct CommandBuilder;
+
+#[derive(Builder)]
+#[builder(name = "CmdOpts", vis = "pub(crate)", derive(Debug, Clone, PartialEq))]
+pub struct Command {
+    executable: String,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable", derive(Clone, Debug))]
+pub struct Job {
+    name: String,
+}
+
+#[derive(Builder)]
+#[builder(typestate, derive(Debug))]
+pub struct Point {
+    x: i32,
+}
+
+fn main() {
+    let _ = CommandBuilder;
+
+    let mut builder: CmdOpts = Command::builder();
+    builder.executable("cargo".to_owned());
+    let snapshot = builder.clone();
+    builder.current_dir("..".to_owned());
+    assert_ne!(builder, snapshot);
+    assert!(format!("{:?}", snapshot).starts_with("CmdOpts"));
+
+    let command = snapshot.clone().build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.current_dir, None);
+
+    let err: CmdOptsError = Command::builder().build().err().unwrap();
+    assert_eq!(err.missing_fields(), ["executable"]);
+
+    let builder = Job::builder().name("test".to_owned());
+    assert!(format!("{:?}", builder.clone()).starts_with("JobBuilder"));
+    assert_eq!(builder.build().unwrap().name, "test");
+
+    let builder = Point::builder().x(1);
+    assert!(format!("{:?}", builder).starts_with("PointBuilder"));
+    assert_eq!(builder.build().x, 1);
+}
@@ -0,0 +1,46 @@
+// A field with #[builder(skip)] has neither a builder field nor a setter, and
+// is initialized with its default value in build. The visibility of setters
+// can be given with #[builder(setter(vis = "..."))] on a field or on the
+// struct, setters take the visibility of their field otherwise.
+
+mod command {
+    use derive_builder::Builder;
+
+    #[derive(Builder)]
+    pub struct Command {
+        pub executable: String,
+        #[builder(setter(vis = "pub(crate)"))]
+        pub jobs: u32,
+        #[builder(skip)]
+        pub cache: Vec<String>,
+        #[builder(skip, default = "7")]
+        pub counter: u32,
+    }
+
+    #[derive(Builder)]
+    #[builder(setter(vis = "pub"))]
+    pub struct Point(i32, #[builder(skip)] i32, i32);
+
+    impl Point {
+        pub fn coords(&self) -> (i32, i32, i32) {
+            (self.0, self.1, self.2)
+        }
+    }
+}
+
+use command::{Command, Point};
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .jobs(2)
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.jobs, 2);
+    assert!(command.cache.is_empty());
+    assert_eq!(command.counter, 7);
+
+    let point = Point::builder()._0(1)._2(3).build().unwrap();
+    assert_eq!(point.coords(), (1, 0, 3));
+}
@@ -0,0 +1,55 @@
+// A field with #[builder(try_setter)] gets a fallible setter next to the plain
+// one. It converts its argument with TryInto and returns the conversion error.
+//
+//     fn try_port<__V: TryInto<u16>>(&mut self, port: __V) -> Result<&mut Self, __V::Error>
+//
+// The fields of tuple structs get try_0, try_1, ...
+
+use derive_builder::Builder;
+use std::num::NonZeroU32;
+
+#[derive(Builder)]
+pub struct Server {
+    #[builder(try_setter)]
+    port: u16,
+    #[builder(try_setter)]
+    workers: Option<NonZeroU32>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Point {
+    #[builder(try_setter)]
+    x: i8,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Job {
+    #[builder(try_setter)]
+    retries: u8,
+}
+
+#[derive(Builder)]
+pub struct Rgb(#[builder(try_setter)] u8, u8, u8);
+
+fn main() {
+    let mut builder = Server::builder();
+    builder.try_port(8080u32).unwrap().try_workers(4u32).unwrap();
+    assert!(builder.try_port(70000u32).is_err());
+    assert!(builder.try_workers(0u32).is_err());
+    let server = builder.build().unwrap();
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.workers, NonZeroU32::new(4));
+
+    let point = Point::builder().try_x(-1i64).unwrap().build();
+    assert_eq!(point.x, -1);
+    assert!(Point::builder().try_x(1000).is_err());
+
+    let job = Job::builder().try_retries(3u64).unwrap().build().unwrap();
+    assert_eq!(job.retries, 3);
+
+    let rgb = Rgb::builder().try_0(255u32).unwrap()._1(0)._2(0).build().unwrap();
+    assert_eq!(rgb.0, 255);
+    assert!(Rgb::builder().try_0(256u32).is_err());
+}
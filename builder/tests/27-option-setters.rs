@@ -0,0 +1,66 @@
+// Option fields get two more setters, one taking an Option and one clearing
+// the field. With #[builder(setter(strip_option = false))] the setter itself
+// takes the Option.
+//
+//     fn maybe_current_dir(&mut self, current_dir: Option<String>) -> &mut Self
+//     fn unset_current_dir(&mut self) -> &mut Self
+//
+// The fields of tuple structs get maybe_0, unset_0, ...
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    current_dir: Option<String>,
+    #[builder(setter(into))]
+    env: Option<String>,
+    #[builder(setter(strip_option = false))]
+    jobs: Option<u32>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Point {
+    x: i32,
+    label: Option<String>,
+}
+
+#[derive(Builder)]
+pub struct Label(Option<String>);
+
+fn main() {
+    let flag: Option<String> = Some("/tmp".to_owned());
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .maybe_current_dir(flag)
+        .maybe_env(Some("RUST_LOG=debug"))
+        .jobs(Some(4))
+        .build()
+        .unwrap();
+    assert_eq!(command.current_dir.as_deref(), Some("/tmp"));
+    assert_eq!(command.env.as_deref(), Some("RUST_LOG=debug"));
+    assert_eq!(command.jobs, Some(4));
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir("/tmp".to_owned())
+        .unset_current_dir()
+        .maybe_env(None::<&str>)
+        .jobs(Some(4))
+        .unset_jobs()
+        .build()
+        .unwrap();
+    assert_eq!(command.current_dir, None);
+    assert_eq!(command.env, None);
+    assert_eq!(command.jobs, None);
+
+    let point = Point::builder().maybe_label(Some("a".to_owned())).x(1).unset_label().build();
+    assert_eq!(point.x, 1);
+    assert_eq!(point.label, None);
+
+    let label = Label::builder().maybe_0(Some("a".to_owned())).build().unwrap();
+    assert_eq!(label.0.as_deref(), Some("a"));
+    let label = Label::builder()._0("a".to_owned()).unset_0().build().unwrap();
+    assert_eq!(label.0, None);
+}
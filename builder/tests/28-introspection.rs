@@ -0,0 +1,98 @@
+// The builder tells which fields are set before build is called. Required
+// fields that are not set are listed by missing_fields, a field with a sub
+// builder is listed while its builder misses fields. Unless the builder
+// derives Debug, its Debug output marks each field as set or unset.
+//
+//     pub fn missing_fields(&self) -> Vec<&'static str>
+//     pub fn is_executable_set(&self) -> bool
+//
+// The fields of tuple structs get is_0_set, is_1_set, ... and raw identifiers
+// lose their r#, is_type_set.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Limits {
+    max_mem: u64,
+}
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(sub_builder)]
+    limits: Limits,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Builder)]
+pub struct Pair(u32, u32);
+
+#[derive(Builder)]
+pub struct Raw {
+    r#type: String,
+    #[builder(try_setter)]
+    r#in: u8,
+    r#ref: Option<String>,
+    #[builder(each = "item")]
+    r#for: Vec<u8>,
+    #[builder(sub_builder)]
+    r#box: Limits,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct RawPoint {
+    r#type: u8,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    assert_eq!(builder.missing_fields(), ["executable", "limits"]);
+    assert!(!builder.is_executable_set());
+    assert_eq!(
+        format!("{:?}", builder),
+        "CommandBuilder { executable: unset, args: unset, current_dir: unset, limits: unset }",
+    );
+
+    builder.executable("cargo".to_owned()).arg("build".to_owned());
+    builder.limits();
+    assert_eq!(builder.missing_fields(), ["limits"]);
+    builder.limits().max_mem(1024);
+    assert!(builder.missing_fields().is_empty());
+    assert!(builder.is_executable_set());
+    assert!(builder.is_args_set());
+    assert!(!builder.is_current_dir_set());
+    assert_eq!(
+        format!("{:?}", builder),
+        "CommandBuilder { executable: set, args: set, current_dir: unset, limits: set }",
+    );
+
+    let builder = Point::builder().y(2);
+    assert_eq!(builder.missing_fields(), ["x"]);
+    assert_eq!(format!("{:?}", builder), "PointBuilder { x: unset, y: set }");
+
+    let mut builder = Pair::builder();
+    builder._1(2);
+    assert!(!builder.is_0_set());
+    assert!(builder.is_1_set());
+
+    let mut builder = Raw::builder();
+    builder.r#type("a".to_owned()).try_in(1u32).unwrap().unset_ref().extend_for([1, 2]);
+    builder.r#box().max_mem(1);
+    assert!(builder.is_type_set());
+    assert!(!builder.is_ref_set());
+    let raw = builder.build().unwrap();
+    assert_eq!(raw.r#type, "a");
+    assert_eq!(raw.r#for, [1, 2]);
+
+    assert_eq!(RawPoint::builder().r#type(1).build().r#type, 1);
+}
@@ -0,0 +1,80 @@
+// With #[builder(no_std)] the generated code only uses paths into core and
+// alloc, so builders can be derived in no_std crates that link alloc. The
+// error enum implements core::error::Error, stable since Rust 1.81, only with
+// #[builder(no_std(core_error))].
+//
+// The builders below are derived in a module where any path into std fails to
+// resolve.
+
+extern crate alloc;
+
+mod firmware {
+    #[allow(dead_code)]
+    mod std {}
+
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use derive_builder::Builder;
+
+    #[derive(Builder, Clone)]
+    #[builder(no_std)]
+    pub struct Uart {
+        #[builder(try_setter)]
+        pub baud: u32,
+    }
+
+    #[derive(Builder, Clone)]
+    #[builder(no_std(core_error), build_fn(validate = "validate_config"))]
+    pub struct Config {
+        #[builder(setter(into))]
+        pub name: String,
+        #[builder(each = "pin")]
+        pub pins: Vec<u8>,
+        pub label: Option<String>,
+        #[builder(default = "3")]
+        pub retries: u8,
+        #[builder(sub_builder)]
+        pub uart: Uart,
+    }
+
+    fn validate_config(builder: &ConfigBuilder) -> Result<(), String> {
+        match &builder.pins {
+            Some(pins) if pins.len() > 4 => Err(String::from("too many pins")),
+            _ => Ok(()),
+        }
+    }
+
+    #[derive(Builder)]
+    #[builder(no_std, typestate)]
+    pub struct Point {
+        pub x: i32,
+    }
+}
+
+use firmware::{Config, ConfigBuilderError, Point};
+
+fn assert_error<E: core::error::Error>() {}
+
+fn main() {
+    let mut builder = Config::builder();
+    builder.name("mcu").pin(1).pin(2);
+    builder.uart().try_baud(115200u64).unwrap();
+    let config = builder.build().unwrap();
+    assert_eq!(config.pins, [1, 2]);
+    assert_eq!(config.retries, 3);
+    assert_eq!(config.uart.baud, 115200);
+
+    let mut builder = config.to_builder();
+    builder.pin(3).pin(4).pin(5);
+    let err = builder.build().err().unwrap();
+    assert_eq!(err.to_string(), "too many pins");
+
+    let builder = Config::builder();
+    assert_eq!(builder.missing_fields(), ["name", "uart"]);
+    assert!(format!("{:?}", builder).starts_with("ConfigBuilder { name: unset"));
+    let err = Config::builder().build().err().unwrap();
+    assert_eq!(err.missing_fields(), ["name", "uart.baud"]);
+    assert_error::<ConfigBuilderError>();
+
+    assert_eq!(Point::builder().x(1).build().x, 1);
+}
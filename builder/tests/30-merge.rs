@@ -0,0 +1,58 @@
+// Builders can be layered with merge, the fields set in the merged builder
+// override those of the builder. Each fields are replaced unless they have
+// #[builder(merge = "append")], and sub builders are merged field by field.
+//
+//     pub fn merge(&mut self, other: CommandBuilder) -> &mut Self
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Limits {
+    max_mem: u64,
+    #[builder(default = "1")]
+    max_jobs: u32,
+}
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg", merge = "append")]
+    args: Vec<String>,
+    #[builder(each = "env", merge = "replace")]
+    env: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(sub_builder)]
+    limits: Limits,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Job {
+    name: String,
+}
+
+fn main() {
+    let mut defaults = Command::builder();
+    defaults.executable("cargo".to_owned()).arg("build".to_owned()).env("A=1".to_owned());
+    defaults.current_dir("/".to_owned());
+    defaults.limits().max_mem(1024).max_jobs(2);
+
+    let mut file = Command::builder();
+    file.arg("--release".to_owned()).env("B=2".to_owned());
+    file.limits().max_mem(2048);
+
+    let mut cli = Command::builder();
+    cli.executable("cross".to_owned());
+
+    let command = defaults.merge(file).merge(cli).build().unwrap();
+    assert_eq!(command.executable, "cross");
+    assert_eq!(command.args, ["build", "--release"]);
+    assert_eq!(command.env, ["B=2"]);
+    assert_eq!(command.current_dir.as_deref(), Some("/"));
+    assert_eq!(command.limits.max_mem, 2048);
+    assert_eq!(command.limits.max_jobs, 2);
+
+    let mut job = Job::builder();
+    job.merge(Job::builder().name("test".to_owned()));
+    assert_eq!(job.build().unwrap().name, "test");
+}
@@ -0,0 +1,63 @@
+//! Docs of the fields are forwarded to the builder fields and to the setters,
+//! and every other generated item gets a default doc, so builders can be
+//! derived in crates denying missing docs. Fields of tuple structs and enum
+//! variants, which need no docs, also get a default doc in the builder.
+
+#![deny(missing_docs)]
+
+use derive_builder::Builder;
+
+/// A command to run.
+#[derive(Builder)]
+#[builder(build_fn(validate = "validate"))]
+pub struct Command {
+    /// Program to run.
+    pub executable: String,
+    /// Arguments passed to the program.
+    #[builder(each = "arg")]
+    pub args: Vec<String>,
+    /// Directory to run the program in.
+    #[builder(try_setter)]
+    pub current_dir: Option<String>,
+    /// Limits of the process.
+    #[builder(sub_builder)]
+    pub limits: Limits,
+}
+
+/// Limits of a process.
+#[derive(Builder)]
+#[builder(from_env(prefix = "APP_"))]
+pub struct Limits {
+    /// Memory in bytes.
+    pub max_mem: u64,
+}
+
+/// A point.
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Point {
+    /// Horizontal coordinate.
+    pub x: i32,
+}
+
+/// A shape.
+#[derive(Builder)]
+pub enum Shape {
+    /// A circle.
+    Circle {
+        /// Radius of the circle.
+        radius: f64,
+    },
+    /// A square.
+    Square(f64),
+}
+
+/// A pair of values.
+#[derive(Builder)]
+pub struct Pair(pub u8, pub u8);
+
+fn validate(_: &CommandBuilder) -> Result<(), String> {
+    Ok(())
+}
+
+fn main() {}
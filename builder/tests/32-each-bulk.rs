@@ -0,0 +1,63 @@
+// Fields with the each attribute can be extended with many items at once and
+// be cleared. The setter of the whole collection is kept when the each setter
+// has another name.
+//
+//     fn arg(&mut self, arg: String) -> &mut Self
+//     fn args(&mut self, args: Vec<String>) -> &mut Self
+//     fn extend_args(&mut self, args: impl IntoIterator<Item = String>) -> &mut Self
+//     fn clear_args(&mut self) -> &mut Self
+//
+// The fields of tuple structs get extend_0, clear_0, ...
+
+use derive_builder::Builder;
+use std::collections::HashMap;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env", setter(into))]
+    env: HashMap<String, String>,
+}
+
+#[derive(Builder)]
+pub struct Args(#[builder(each = "arg")] Vec<String>);
+
+fn main() {
+    let command = Command::builder()
+        .arg("build".to_owned())
+        .extend_args(vec!["--release".to_owned(), "--locked".to_owned()])
+        .extend_env([("A", "1"), ("B", "2")])
+        .env("C", "3")
+        .build()
+        .unwrap();
+    assert_eq!(command.args, ["build", "--release", "--locked"]);
+    assert_eq!(command.env.len(), 3);
+    assert_eq!(command.env["B"], "2");
+
+    let command = Command::builder()
+        .arg("build".to_owned())
+        .clear_args()
+        .env("C", "3")
+        .clear_env()
+        .build()
+        .unwrap();
+    assert!(command.args.is_empty());
+    assert!(command.env.is_empty());
+
+    let command = Command::builder()
+        .arg("build".to_owned())
+        .args(vec!["test".to_owned()])
+        .arg("--quiet".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.args, ["test", "--quiet"]);
+
+    let args = Args::builder()
+        .arg("a".to_owned())
+        .clear_0()
+        .extend_0(vec!["b".to_owned(), "c".to_owned()])
+        .build()
+        .unwrap();
+    assert_eq!(args.0, ["b", "c"]);
+}
@@ -0,0 +1,30 @@
+// Every malformed builder attribute is reported in one compile, pointing at
+// the offending name and listing the valid ones, with a suggestion when the
+// name looks like a typo. The values of the attributes on the struct, or on a
+// field, are checked as soon as their names are all known.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestat, no_std(core_eror))]
+pub struct Command {
+    #[builder(eachh = "arg")]
+    args: Vec<String>,
+    #[builder(setter(int))]
+    env: Vec<String>,
+    #[builder(unknown)]
+    current_dir: Option<String>,
+    #[builder(default = 1)]
+    retries: u8,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "borrowed")]
+pub struct Job {
+    #[builder(each = 1)]
+    args: Vec<String>,
+    #[builder(skip = "yes")]
+    cache: Vec<String>,
+}
+
+fn main() {}
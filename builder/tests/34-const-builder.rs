@@ -0,0 +1,83 @@
+// With #[builder(const)] the builder uses the owned pattern, builder(), the
+// setters and build are const fn, and build returns the struct directly. A
+// missing required field panics, which fails the compilation when the builder
+// is evaluated in a constant. Fields may have destructors, such as String.
+//
+//     pub const fn builder() -> CommandBuilder
+//     const fn executable(self, executable: &'static str) -> Self
+//     pub const fn build(self) -> Command
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(const)]
+pub struct Command {
+    executable: &'static str,
+    args: &'static [&'static str],
+    current_dir: Option<&'static str>,
+    #[builder(default = "3")]
+    retries: u8,
+    timeout: Option<u32>,
+    #[builder(skip, default = "true")]
+    enabled: bool,
+}
+
+#[derive(Builder)]
+#[builder(const)]
+pub struct Named {
+    name: String,
+    label: Option<String>,
+    #[builder(default = "String::new()")]
+    alias: String,
+}
+
+static ANONYMOUS: Named = Named::builder().name(String::new()).build();
+
+static DEFAULT: Command = Command::builder()
+    .executable("cargo")
+    .args(&["build", "--release"])
+    .build();
+
+const NIGHTLY: Command = Command::builder()
+    .executable("cargo")
+    .executable("rustup")
+    .args(&["run", "nightly", "cargo"])
+    .current_dir("/tmp")
+    .retries(5)
+    .timeout(30)
+    .unset_timeout()
+    .maybe_timeout(Some(60))
+    .build();
+
+fn main() {
+    assert_eq!(DEFAULT.executable, "cargo");
+    assert_eq!(DEFAULT.args, ["build", "--release"]);
+    assert_eq!(DEFAULT.current_dir, None);
+    assert_eq!(DEFAULT.retries, 3);
+    assert_eq!(DEFAULT.timeout, None);
+    assert!(DEFAULT.enabled);
+
+    assert_eq!(NIGHTLY.executable, "rustup");
+    assert_eq!(NIGHTLY.current_dir, Some("/tmp"));
+    assert_eq!(NIGHTLY.retries, 5);
+    assert_eq!(NIGHTLY.timeout, Some(60));
+
+    let command = Command::builder().executable("ls").args(&[]).build();
+    assert_eq!(command.executable, "ls");
+    assert_eq!(command.retries, 3);
+
+    let builder = Command::builder().executable("ls");
+    assert!(!builder.is_args_set());
+    assert_eq!(builder.missing_fields(), ["args"]);
+
+    assert_eq!(ANONYMOUS.name, "");
+    assert_eq!(ANONYMOUS.label, None);
+    let named = Named::builder()
+        .name("a".to_owned())
+        .name("b".to_owned())
+        .label("c".to_owned())
+        .build();
+    assert_eq!(named.name, "b");
+    assert_eq!(named.label.as_deref(), Some("c"));
+    assert_eq!(named.alias, "");
+}
@@ -0,0 +1,17 @@
+// A const builder evaluated in a constant fails the compilation when a
+// required field is not set.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(const)]
+pub struct Command {
+    executable: &'static str,
+    args: &'static [&'static str],
+}
+
+static DEFAULT: Command = Command::builder().executable("cargo").build();
+
+fn main() {
+    let _ = &DEFAULT;
+}
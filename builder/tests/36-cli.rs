@@ -0,0 +1,99 @@
+// With #[builder(cli)] the builder can be read from command-line arguments,
+// one flag per setter parsed with FromStr. Flags of each fields can be
+// repeated, bool fields are set by a flag without value and are false without
+// it unless they have a default, and `--help` returns
+// the help built from the docs of the fields. The fields of tuple structs are
+// read from --0, --1, ...
+//
+//     pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<CommandBuilder, CommandBuilderError>
+//     pub fn help() -> &'static str
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(cli)]
+pub struct Command {
+    /// Program to run.
+    executable: String,
+    /// Arguments of the program.
+    ///
+    /// Given in order.
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    /// Directory to run the program in.
+    current_dir: Option<String>,
+    #[builder(default = "1")]
+    jobs: u32,
+    /// Prints each step.
+    #[builder(default)]
+    verbose: bool,
+    /// Only prints the steps.
+    dry_run: bool,
+}
+
+#[derive(Builder)]
+#[builder(cli)]
+pub struct Size(u32, u32);
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn main() {
+    let command = CommandBuilder::parse_args(args(&[
+        "--executable", "cargo",
+        "--arg", "build",
+        "--arg=--release",
+        "--verbose",
+        "--current-dir", "/tmp",
+    ]))
+    .unwrap()
+    .build()
+    .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, ["build", "--release"]);
+    assert_eq!(command.current_dir.as_deref(), Some("/tmp"));
+    assert_eq!(command.jobs, 1);
+    assert!(command.verbose);
+    assert!(!command.dry_run);
+
+    let command = CommandBuilder::parse_args(args(&["--dry-run", "--executable", "ls"]))
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "ls");
+    assert!(command.dry_run);
+
+    // The arguments can be layered over other settings.
+    let mut builder = Command::builder();
+    builder.executable("make".to_owned()).jobs(4);
+    builder.merge(CommandBuilder::parse_args(args(&["--jobs=8", "--verbose=false"])).unwrap());
+    let command = builder.build().unwrap();
+    assert_eq!(command.executable, "make");
+    assert_eq!(command.jobs, 8);
+    assert!(!command.verbose);
+
+    let err = CommandBuilder::parse_args(args(&["--jobs", "many"])).err().unwrap();
+    assert_eq!(err.to_string(), "invalid value `many` for `jobs`: invalid digit found in string");
+    let err = CommandBuilder::parse_args(args(&["--executable"])).err().unwrap();
+    assert_eq!(err, CommandBuilderError::MissingValue("--executable".to_owned()));
+    let err = CommandBuilder::parse_args(args(&["build"])).err().unwrap();
+    assert_eq!(err, CommandBuilderError::UnknownArgument("build".to_owned()));
+
+    let size = SizeBuilder::parse_args(args(&["--0", "640", "--1=480"])).unwrap().build().unwrap();
+    assert_eq!((size.0, size.1), (640, 480));
+
+    let err = CommandBuilder::parse_args(args(&["--help"])).err().unwrap();
+    assert_eq!(err, CommandBuilderError::Help(CommandBuilder::help().to_owned()));
+    assert_eq!(
+        CommandBuilder::help(),
+        "Options:\n  \
+         --executable <EXECUTABLE>    Program to run. (required)\n  \
+         --arg <ARG>...               Arguments of the program.\n  \
+         --current-dir <CURRENT_DIR>  Directory to run the program in.\n  \
+         --jobs <JOBS>\n  \
+         --verbose                    Prints each step.\n  \
+         --dry-run                    Only prints the steps.\n  \
+         --help                       Prints this help.\n",
+    );
+}
@@ -0,0 +1,12 @@
+// The `--help` flag of #[builder(cli)] is reserved for the help, a field that
+// would take it is an error.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(cli)]
+pub struct Command {
+    help: String,
+}
+
+fn main() {}
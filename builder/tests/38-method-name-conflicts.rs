@@ -0,0 +1,35 @@
+// The setters of the fields cannot take the name of another method of the
+// builder, such as merge or the methods of from_env and cli, nor the name of
+// another setter.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    merge: bool,
+    #[builder(each = "step")]
+    steps: Vec<String>,
+    step: String,
+}
+
+#[derive(Builder)]
+#[builder(from_env)]
+pub struct Config {
+    #[builder(name = "from_map")]
+    map: String,
+    missing_fields: Option<u32>,
+}
+
+#[derive(Builder)]
+#[builder(cli)]
+pub struct Args {
+    r#parse_args: String,
+}
+
+#[derive(Builder)]
+pub enum Shape {
+    Circle { radius: f64 },
+    Rect { build: f64, is_build_set: bool },
+}
+
+fn main() {}
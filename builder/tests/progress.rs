@@ -10,4 +10,33 @@ fn tests() {
     t.pass("tests/07-repeated-field.rs");
     t.compile_fail("tests/08-unrecognized-attribute.rs");
     t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-typestate.rs");
+    t.compile_fail("tests/11-typestate-missing-field.rs");
+    t.pass("tests/12-default-field.rs");
+    t.pass("tests/13-generic-struct.rs");
+    t.pass("tests/14-error-enum.rs");
+    t.pass("tests/15-missing-fields.rs");
+    t.pass("tests/16-setter-into.rs");
+    t.pass("tests/17-validate.rs");
+    t.pass("tests/18-builder-pattern.rs");
+    t.pass("tests/19-tuple-struct-and-enum.rs");
+    t.pass("tests/20-each-collections.rs");
+    t.pass("tests/21-sub-builder.rs");
+    t.pass("tests/22-to-builder.rs");
+    t.pass("tests/23-from-env.rs");
+    t.pass("tests/24-builder-name.rs");
+    t.pass("tests/25-skip-and-setter-vis.rs");
+    t.pass("tests/26-try-setter.rs");
+    t.pass("tests/27-option-setters.rs");
+    t.pass("tests/28-introspection.rs");
+    t.pass("tests/29-no-std.rs");
+    t.pass("tests/30-merge.rs");
+    t.pass("tests/31-docs.rs");
+    t.pass("tests/32-each-bulk.rs");
+    t.compile_fail("tests/33-attribute-diagnostics.rs");
+    t.pass("tests/34-const-builder.rs");
+    t.compile_fail("tests/35-const-builder-missing-field.rs");
+    t.pass("tests/36-cli.rs");
+    t.compile_fail("tests/37-cli-reserved-flag.rs");
+    t.compile_fail("tests/38-method-name-conflicts.rs");
 }